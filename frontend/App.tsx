@@ -13,14 +13,16 @@ import { Header } from './components/Header';
 import { Footer } from './components/Footer';
 import { FilterBar } from './components/FilterBar';
 import { ServiceCard } from './components/ServiceCard';
-import type { OnionService, FilterTag } from './types';
+import type { OnionService, FilterTag, SitesDocument } from './types';
 import servicesData from './data/services.json';
 
 function App() {
   const [searchQuery, setSearchQuery] = useState('');
   const [selectedFilter, setSelectedFilter] = useState<FilterTag>('all');
 
-  const services = servicesData as OnionService[];
+  // Older data files are a bare array of services (schema version 1)
+  const data = servicesData as unknown as OnionService[] | SitesDocument;
+  const services = Array.isArray(data) ? data : data.sites;
 
   const filteredServices = useMemo(() => {
     return services.filter((service) => {
@@ -1,15 +1,176 @@
+export interface Endpoint {
+  url: string;
+  label?: string;
+  status: 'online' | 'offline' | 'unknown' | string;
+  prev_status: string;
+  last_checked: string | null;
+  response_time_ms: number | null;
+  timings?: Timings;
+  certificate?: CertificateCheck;
+  redirects?: RedirectCheck;
+  content?: ContentFingerprint;
+  diagnosis?: Diagnosis;
+  descriptor?: DescriptorCheck;
+  vantages?: VantageResult[];
+}
+
+export interface VantageResult {
+  vantage: string;
+  status: string;
+  response_time_ms: number | null;
+  timings?: Timings;
+  checked_at: string;
+}
+
+// Phases of a check in milliseconds; absent when not measured (e.g. TLS over plain HTTP)
+export interface Timings {
+  socks_handshake_ms?: number;
+  circuit_ms?: number;
+  tls_ms?: number;
+  ttfb_ms?: number;
+  total_ms: number;
+}
+
+export interface CertificateCheck {
+  subject: string;
+  sans?: string[];
+  issuer: string;
+  not_before?: string;
+  not_after?: string;
+  self_signed: boolean;
+  covers_host: boolean;
+  trusted: boolean;
+  verify_error?: string;
+  warnings?: string[];
+  checked_at: string;
+}
+
+export interface RedirectCheck {
+  chain: { url: string; status_code: number; location: string }[];
+  final_url: string;
+  leaves_onion: boolean;
+  looped: boolean;
+  warnings?: string[];
+}
+
+export interface ContentFingerprint {
+  body_hash: string;
+  simhash: string;
+  title?: string;
+  length: number;
+  placeholder?: string;
+  snapshot?: string;
+  change?: string;
+  checked_at: string;
+}
+
+export interface Diagnosis {
+  failed_at: 'descriptor' | 'introduction' | 'rendezvous' | 'circuit' | 'service' | 'unknown';
+  descriptor_found: boolean;
+  hsdirs_queried: number;
+  descriptor_revision?: number;
+  intro_points?: number;
+  circuits: {
+    intro_succeeded: number;
+    intro_failed: number;
+    rend_joined: number;
+    rend_failed: number;
+  };
+  details?: string;
+  checked_at: string;
+}
+
+export interface DescriptorCheck {
+  found: boolean;
+  revision_counter?: number;
+  lifetime_minutes?: number;
+  published_at?: string;
+  signing_key_expires?: string;
+  intro_points?: number;
+  at_risk: boolean;
+  risks?: string[];
+  error?: string;
+  checked_at: string;
+}
+
+export interface OnionLocationCheck {
+  status: 'verified' | 'mismatch' | 'unverified' | 'error';
+  advertised?: string;
+  source?: 'header' | 'meta';
+  error?: string;
+  checked_at: string;
+}
+
+export interface OwnershipCheck {
+  status: 'verified' | 'claimed' | 'mismatch' | 'unverified' | 'error';
+  method?: 'well-known' | 'onion-backlink' | 'dns';
+  details?: string[];
+  checked_at: string;
+  verified_at?: string;
+}
+
 export interface OnionService {
   title: string;
   name: string;
   onion_address: string;
-  status: 'online' | 'offline' | 'unknown' | string; // string for error-XXX statuses
+  status: 'online' | 'partial' | 'offline' | 'unknown' | string; // string for error-XXX statuses
   prev_status: string;
   last_checked: string | null;
+  endpoints?: Endpoint[];
   category?: string;
   description?: string;
   official_website?: string;
   github?: string;
   tags?: string[];
+  extra?: Record<string, unknown>;
+  onion_location?: OnionLocationCheck;
+  ownership?: OwnershipCheck;
+}
+
+export interface StatusCounts {
+  total: number;
+  online: number;
+  partial: number;
+  offline: number;
+  error: number;
+  unknown: number;
+}
+
+export interface RunMetadata {
+  started_at: string;
+  finished_at: string | null;
+  proxy: string;
+  vantages?: string[];
+  tool_version: string;
+  counts: StatusCounts;
+}
+
+export interface SitesDocument {
+  schema_version: number;
+  run?: RunMetadata;
+  sites: OnionService[];
+}
+
+export interface Incident {
+  started_at: string;
+  ended_at: string | null;
+  status: string;
+  checks: number;
+}
+
+export interface FrontendService extends OnionService {
+  uptime: {
+    day: number | null;
+    week: number | null;
+    month: number | null;
+    quarter: number | null;
+  };
+  last_change: string | null;
+  incidents: {
+    last_30_days: number;
+    ongoing: Incident | null;
+    last_resolved: Incident | null;
+  };
 }
 
 export type FilterTag = 'all' | 'online' | 'offline' | 'unknown';
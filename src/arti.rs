@@ -0,0 +1,237 @@
+use anyhow::{Context, Result};
+use arti_client::config::TorClientConfigBuilder;
+use arti_client::{ErrorKind, HasKind, TorClient};
+use chrono::Utc;
+use hyper::body::HttpBody;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tor_rtcompat::PreferredRuntime;
+
+use crate::control::{CircuitSummary, Diagnosis, FailureStage};
+use crate::timing::Timings;
+use crate::HttpResponse;
+
+/// Time allowed for a request, like curl's `--max-time 60`
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+/// Time allowed for the first bootstrap, which downloads the directory
+const BOOTSTRAP_TIMEOUT: Duration = Duration::from_secs(180);
+
+/// A Tor client embedded in the monitor, used instead of a SOCKS proxy and curl
+#[derive(Clone)]
+pub struct EmbeddedArti {
+    client: Arc<TorClient<PreferredRuntime>>,
+    state_dir: PathBuf,
+}
+
+impl std::fmt::Debug for EmbeddedArti {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EmbeddedArti").field("state_dir", &self.state_dir).finish_non_exhaustive()
+    }
+}
+
+impl EmbeddedArti {
+    /// Client keeping its state in `ARTI_STATE_DIR` and its directory cache in `ARTI_CACHE_DIR`
+    /// (by default `arti/state` and `arti/cache` in the data directory). Not bootstrapped yet.
+    pub fn from_env() -> Result<EmbeddedArti> {
+        let (state_dir, cache_dir) = directories_from_env(&crate::data_dir());
+        let config = TorClientConfigBuilder::from_directories(&state_dir, &cache_dir)
+            .build()
+            .context("Invalid Arti configuration")?;
+        let client = TorClient::builder()
+            .config(config)
+            .create_unbootstrapped()
+            .with_context(|| format!("Failed to create the Arti client in {}", state_dir.display()))?;
+        Ok(EmbeddedArti { client, state_dir })
+    }
+
+    pub fn state_dir(&self) -> &Path {
+        &self.state_dir
+    }
+
+    /// Connect to the Tor network; otherwise every site would be reported offline
+    pub async fn bootstrap(&self) -> Result<()> {
+        tokio::time::timeout(BOOTSTRAP_TIMEOUT, self.client.bootstrap())
+            .await
+            .context("Timed out bootstrapping the Arti client")?
+            .context("Failed to bootstrap the Arti client")
+    }
+
+    /// Request `url` once, without following redirects. A failed request comes back as the
+    /// step it failed at, which Arti's error already tells without a control port.
+    pub async fn request(&self, url: &str) -> Result<HttpResponse, Diagnosis> {
+        match tokio::time::timeout(REQUEST_TIMEOUT, self.try_request(url)).await {
+            Ok(result) => result,
+            Err(_) => Err(diagnosis(FailureStage::Unknown, "Request timed out".to_string())),
+        }
+    }
+
+    async fn try_request(&self, url: &str) -> Result<HttpResponse, Diagnosis> {
+        let service_failure = |e: anyhow::Error| diagnosis(FailureStage::Service, format!("{:#}", e));
+        let url = reqwest::Url::parse(url).map_err(|e| diagnosis(FailureStage::Unknown, format!("Invalid URL: {}", e)))?;
+        let host = url.host_str().unwrap_or_default().to_string();
+        let port = url.port_or_known_default().unwrap_or(80);
+
+        let start = Instant::now();
+        let stream = self.client.connect((host.as_str(), port)).await.map_err(|e| {
+            diagnosis(failure_stage(e.kind()), format!("{:#}", anyhow::Error::from(e)))
+        })?;
+        let circuit_ms = elapsed_ms(start);
+
+        let (mut response, tls_ms) = if url.scheme() == "https" {
+            // Certificate problems must not fail the check; only curl reports them
+            let connector = tokio_native_tls::native_tls::TlsConnector::builder()
+                .danger_accept_invalid_certs(true)
+                .danger_accept_invalid_hostnames(true)
+                .build()
+                .map_err(|e| service_failure(e.into()))?;
+            let stream = tokio_native_tls::TlsConnector::from(connector)
+                .connect(&host, stream)
+                .await
+                .map_err(|e| service_failure(anyhow::Error::from(e).context("TLS handshake failed")))?;
+            let tls_ms = elapsed_ms(start);
+            (exchange(stream, &url).await.map_err(service_failure)?, Some(tls_ms))
+        } else {
+            (exchange(stream, &url).await.map_err(service_failure)?, None)
+        };
+        let ttfb_ms = elapsed_ms(start);
+
+        let redirect_url = response
+            .headers()
+            .get(hyper::header::LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .and_then(|location| url.join(location).ok())
+            .map(String::from);
+        let mut body = Vec::new();
+        while let Some(Ok(chunk)) = response.body_mut().data().await {
+            body.extend_from_slice(&chunk);
+            if body.len() as u64 >= crate::MAX_BODY_BYTES {
+                body.truncate(crate::MAX_BODY_BYTES as usize);
+                break;
+            }
+        }
+
+        Ok(HttpResponse {
+            code: response.status().as_u16(),
+            timings: Timings {
+                socks_handshake_ms: None,
+                circuit_ms: Some(circuit_ms),
+                tls_ms: tls_ms.map(|tls| tls - circuit_ms),
+                ttfb_ms: Some(ttfb_ms - tls_ms.unwrap_or(circuit_ms)),
+                total_ms: elapsed_ms(start),
+            },
+            redirect_url,
+            certs: String::new(),
+            body,
+        })
+    }
+}
+
+/// Send a GET request for `url` over `stream` and wait for the response headers
+async fn exchange<S>(stream: S, url: &reqwest::Url) -> Result<hyper::Response<hyper::Body>>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (mut sender, connection) = hyper::client::conn::handshake(stream).await.context("HTTP handshake failed")?;
+    tokio::spawn(connection);
+
+    let host = match url.port() {
+        Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+        None => url.host_str().unwrap_or_default().to_string(),
+    };
+    let path = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+    let request = hyper::Request::get(path)
+        .header(hyper::header::HOST, host)
+        .header(hyper::header::USER_AGENT, "onion-monitoring-tool")
+        .body(hyper::Body::empty())?;
+    sender.send_request(request).await.context("HTTP request failed")
+}
+
+/// `ARTI_STATE_DIR` and `ARTI_CACHE_DIR`, defaulting to directories under `data_dir`
+fn directories_from_env(data_dir: &Path) -> (PathBuf, PathBuf) {
+    let dir = |var: &str, default: &str| {
+        std::env::var(var)
+            .ok()
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| data_dir.join("arti").join(default))
+    };
+    (dir("ARTI_STATE_DIR", "state"), dir("ARTI_CACHE_DIR", "cache"))
+}
+
+/// Step a connection failed at, going by the kind of Arti error
+fn failure_stage(kind: ErrorKind) -> FailureStage {
+    match kind {
+        ErrorKind::OnionServiceNotFound
+        | ErrorKind::OnionServiceMissingClientAuth
+        | ErrorKind::OnionServiceWrongClientAuth
+        | ErrorKind::OnionServiceAddressInvalid => FailureStage::Descriptor,
+        ErrorKind::OnionServiceNotRunning => FailureStage::Introduction,
+        ErrorKind::OnionServiceConnectionFailed | ErrorKind::OnionServiceProtocolViolation => FailureStage::Rendezvous,
+        ErrorKind::TorAccessFailed
+        | ErrorKind::BootstrapRequired
+        | ErrorKind::TorNetworkTimeout
+        | ErrorKind::CircuitCollapse
+        | ErrorKind::NoPath => FailureStage::Circuit,
+        ErrorKind::RemoteConnectionRefused
+        | ErrorKind::RemoteStreamClosed
+        | ErrorKind::RemoteStreamReset
+        | ErrorKind::RemoteStreamError => FailureStage::Service,
+        _ => FailureStage::Unknown,
+    }
+}
+
+fn diagnosis(failed_at: FailureStage, details: String) -> Diagnosis {
+    Diagnosis {
+        failed_at,
+        // Any later step needs the descriptor
+        descriptor_found: matches!(
+            failed_at,
+            FailureStage::Introduction | FailureStage::Rendezvous | FailureStage::Service
+        ),
+        hsdirs_queried: 0,
+        descriptor_revision: None,
+        intro_points: None,
+        circuits: CircuitSummary::default(),
+        details: Some(details),
+        checked_at: Utc::now(),
+    }
+}
+
+fn elapsed_ms(start: Instant) -> u64 {
+    start.elapsed().as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_errors_to_the_failed_step() {
+        assert_eq!(failure_stage(ErrorKind::OnionServiceNotFound), FailureStage::Descriptor);
+        assert_eq!(failure_stage(ErrorKind::OnionServiceMissingClientAuth), FailureStage::Descriptor);
+        assert_eq!(failure_stage(ErrorKind::OnionServiceNotRunning), FailureStage::Introduction);
+        assert_eq!(failure_stage(ErrorKind::OnionServiceConnectionFailed), FailureStage::Rendezvous);
+        assert_eq!(failure_stage(ErrorKind::TorNetworkTimeout), FailureStage::Circuit);
+        assert_eq!(failure_stage(ErrorKind::RemoteConnectionRefused), FailureStage::Service);
+        assert_eq!(failure_stage(ErrorKind::Other), FailureStage::Unknown);
+
+        assert!(!diagnosis(FailureStage::Descriptor, String::new()).descriptor_found);
+        assert!(diagnosis(FailureStage::Introduction, String::new()).descriptor_found);
+    }
+
+    #[test]
+    fn keeps_arti_directories_in_the_data_directory() {
+        let (state, cache) = directories_from_env(Path::new("/app"));
+        if std::env::var_os("ARTI_STATE_DIR").is_none() {
+            assert_eq!(state, Path::new("/app/arti/state"));
+        }
+        if std::env::var_os("ARTI_CACHE_DIR").is_none() {
+            assert_eq!(cache, Path::new("/app/arti/cache"));
+        }
+    }
+}
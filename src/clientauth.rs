@@ -0,0 +1,169 @@
+use anyhow::{anyhow, bail, Context, Result};
+use data_encoding::{BASE32_NOPAD, BASE64};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use crate::descriptor;
+use crate::OnionSite;
+
+/// An x25519 client authorization key for one onion service. The key is deliberately not
+/// serializable and is redacted from `Debug`, so it cannot end up in any output file.
+#[derive(Clone)]
+pub struct ClientAuthKey {
+    /// Service ID (onion address without `.onion`) the key is for
+    pub service_id: String,
+    secret: [u8; 32],
+}
+
+impl fmt::Debug for ClientAuthKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientAuthKey")
+            .field("service_id", &self.service_id)
+            .field("secret", &"<redacted>")
+            .finish()
+    }
+}
+
+impl ClientAuthKey {
+    pub fn secret(&self) -> &[u8; 32] {
+        &self.secret
+    }
+
+    /// Key argument for the control port's `ONION_CLIENT_AUTH_ADD`
+    pub fn control_argument(&self) -> String {
+        format!("x25519:{}", BASE64.encode(&self.secret))
+    }
+}
+
+/// Client authorization keys of the monitored services, by onion identity key
+#[derive(Debug, Default)]
+pub struct ClientAuthKeys {
+    keys: HashMap<[u8; 32], ClientAuthKey>,
+}
+
+impl ClientAuthKeys {
+    /// Read the key files named in `CLIENT_AUTH_KEYS`, a comma-separated list of
+    /// `<site name>=<path>` pairs, and match them to the sites' endpoints
+    pub fn from_env(sites: &[OnionSite]) -> Result<Self> {
+        let Ok(value) = std::env::var("CLIENT_AUTH_KEYS") else {
+            return Ok(ClientAuthKeys::default());
+        };
+
+        let mut keys = ClientAuthKeys::default();
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (name, path) = entry.split_once('=')
+                .ok_or_else(|| anyhow!("Invalid CLIENT_AUTH_KEYS entry '{}', expected <site name>=<path>", entry))?;
+            let name = name.trim();
+            let Some(site) = sites.iter().find(|s| s.name == name) else {
+                println!("⚠ Warning: CLIENT_AUTH_KEYS names unknown site '{}'", name);
+                continue;
+            };
+            let addresses: Vec<&str> = site.endpoints.iter().map(|e| e.url.as_str()).collect();
+            for key in load_key_file(Path::new(path.trim()), &addresses)? {
+                let identity_key = descriptor::identity_key(&key.service_id)?;
+                keys.keys.insert(identity_key, key);
+            }
+        }
+        Ok(keys)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ClientAuthKey> {
+        self.keys.values()
+    }
+
+    /// Key for the onion service behind `url`
+    pub fn for_url(&self, url: &str) -> Option<&ClientAuthKey> {
+        self.keys.get(&descriptor::identity_key(url).ok()?)
+    }
+}
+
+/// Read a key file for a site with the given endpoint `addresses`.
+///
+/// Accepts Tor's `.auth_private` format (`<service id>:descriptor:x25519:<base32 key>`), which
+/// must name one of the site's services, or a bare base32 key used for all of them.
+fn load_key_file(path: &Path, addresses: &[&str]) -> Result<Vec<ClientAuthKey>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read client auth key {}", path.display()))?;
+    parse_key_file(&text, addresses)
+        .with_context(|| format!("Invalid client auth key {}", path.display()))
+}
+
+fn parse_key_file(text: &str, addresses: &[&str]) -> Result<Vec<ClientAuthKey>> {
+    let service_ids: Vec<String> = addresses.iter().filter_map(|a| descriptor::service_id(a).ok()).collect();
+    let line = text.lines().map(str::trim).find(|l| !l.is_empty() && !l.starts_with('#')).unwrap_or_default();
+
+    let (services, key) = match line.split(':').collect::<Vec<_>>()[..] {
+        [service, "descriptor", "x25519", key] => {
+            let service_id = descriptor::service_id(service)?;
+            if !service_ids.contains(&service_id) {
+                bail!("The key is for {}.onion, which is not an endpoint of the site", service_id);
+            }
+            (vec![service_id], key)
+        }
+        [key] if !key.is_empty() => (service_ids, key),
+        _ => bail!("Expected <service id>:descriptor:x25519:<key> or a bare base32 key"),
+    };
+
+    // Do not echo the key in errors
+    let secret: [u8; 32] = BASE32_NOPAD.decode(key.to_ascii_uppercase().as_bytes())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow!("The key is not a base32 x25519 private key"))?;
+
+    Ok(services.into_iter().map(|service_id| ClientAuthKey { service_id, secret }).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::descriptor::testing;
+
+    #[test]
+    fn parses_tor_auth_private_files() {
+        let onion = testing::onion_address();
+        let id = onion.trim_end_matches(".onion");
+        let key = BASE32_NOPAD.encode(&[1; 32]);
+        let url = format!("http://{}", onion);
+
+        let keys = parse_key_file(&format!("{}:descriptor:x25519:{}\n", id, key), &[&url]).unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].service_id, id);
+        assert_eq!(keys[0].secret(), &[1; 32]);
+        assert_eq!(keys[0].control_argument(), format!("x25519:{}", BASE64.encode(&[1; 32])));
+
+        // A bare key applies to every endpoint of the site
+        let mirror = testing::onion_address_of(&[8; 32]);
+        let keys = parse_key_file(&key.to_ascii_lowercase(), &[&url, &format!("http://{}", mirror)]).unwrap();
+        let services: Vec<&str> = keys.iter().map(|k| k.service_id.as_str()).collect();
+        assert_eq!(services, [id, mirror.trim_end_matches(".onion")]);
+        assert!(keys.iter().all(|k| k.secret() == &[1; 32]));
+    }
+
+    #[test]
+    fn rejects_keys_for_other_services() {
+        let onion = testing::onion_address();
+        let key = BASE32_NOPAD.encode(&[1; 32]);
+        let line = format!("{}:descriptor:x25519:{}", onion.trim_end_matches(".onion"), key);
+
+        assert!(parse_key_file(&line, &["http://example.onion"]).is_err());
+        let error = parse_key_file("not-a-key", &[&format!("http://{}", onion)]).unwrap_err();
+        assert!(!format!("{:#}", error).contains("not-a-key"));
+    }
+
+    #[test]
+    fn never_shows_the_secret() {
+        let key = ClientAuthKey { service_id: "abc".to_string(), secret: [1; 32] };
+        let debug = format!("{:?}", key);
+        assert!(debug.contains("<redacted>"));
+        assert!(!debug.contains("1, 1"));
+    }
+}
@@ -0,0 +1,586 @@
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use data_encoding::HEXUPPER;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::time::{timeout, Instant};
+
+use crate::clientauth::ClientAuthKeys;
+use crate::descriptor;
+use crate::proxy::SocksProxy;
+
+/// How long to wait for the HSDirs to answer an `HSFETCH`
+const DESCRIPTOR_TIMEOUT: Duration = Duration::from_secs(60);
+/// How long the connection probe may take
+const PROBE_TIMEOUT: Duration = Duration::from_secs(90);
+/// Time allowed for circuit events to arrive after the probe finished
+const EVENT_GRACE: Duration = Duration::from_secs(2);
+
+/// Where to reach the Tor control port, from `TOR_CONTROL`
+#[derive(Debug, Clone)]
+pub struct ControlConfig {
+    pub address: String,
+    pub auth: ControlAuth,
+}
+
+#[derive(Debug, Clone)]
+pub enum ControlAuth {
+    None,
+    /// `HashedControlPassword`, from `TOR_CONTROL_PASSWORD`
+    Password(String),
+    /// `CookieAuthentication`, from the file in `TOR_CONTROL_COOKIE`
+    Cookie(PathBuf),
+}
+
+impl ControlConfig {
+    /// `None` unless `TOR_CONTROL` is set, which turns diagnostics on
+    pub fn from_env() -> Option<Self> {
+        let address = std::env::var("TOR_CONTROL").ok().filter(|a| !a.trim().is_empty())?;
+        let auth = if let Ok(password) = std::env::var("TOR_CONTROL_PASSWORD") {
+            ControlAuth::Password(password)
+        } else if let Ok(cookie) = std::env::var("TOR_CONTROL_COOKIE") {
+            ControlAuth::Cookie(PathBuf::from(cookie))
+        } else {
+            ControlAuth::None
+        };
+        Some(ControlConfig { address: address.trim().to_string(), auth })
+    }
+}
+
+/// Why a failed endpoint could not be reached, as seen by Tor
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct Diagnosis {
+    /// Step at which the connection failed
+    pub failed_at: FailureStage,
+    /// Whether any HSDir returned the descriptor
+    pub descriptor_found: bool,
+    /// HSDirs asked for the descriptor
+    pub hsdirs_queried: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub descriptor_revision: Option<u64>,
+    /// Introduction points listed in the descriptor; `None` if unknown (e.g. client authorization)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intro_points: Option<usize>,
+    pub circuits: CircuitSummary,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum FailureStage {
+    /// No HSDir had the descriptor, or it could not be read
+    Descriptor,
+    /// No introduction point accepted the introduction
+    Introduction,
+    /// The service did not complete the rendezvous
+    Rendezvous,
+    /// The client could not build circuits at all
+    Circuit,
+    /// Tor connected to the service; the failure is in the service itself (HTTP, TLS, ...)
+    Service,
+    /// The control port could not be used
+    Unknown,
+}
+
+impl FailureStage {
+    pub fn label(self) -> &'static str {
+        match self {
+            FailureStage::Descriptor => "descriptor lookup",
+            FailureStage::Introduction => "introduction",
+            FailureStage::Rendezvous => "rendezvous",
+            FailureStage::Circuit => "circuit building",
+            FailureStage::Service => "the service (Tor connected)",
+            FailureStage::Unknown => "an unknown step",
+        }
+    }
+}
+
+/// Onion service circuits seen while probing the service
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Default)]
+pub struct CircuitSummary {
+    pub intro_succeeded: usize,
+    pub intro_failed: usize,
+    pub rend_joined: usize,
+    pub rend_failed: usize,
+}
+
+/// One control protocol reply or asynchronous event
+#[derive(Debug)]
+struct Message {
+    code: u16,
+    /// Text of the first line, after the status code
+    text: String,
+    /// Contents of the first data block (`XYZ+` lines), with dot-stuffing removed
+    data: Option<String>,
+}
+
+/// Read control protocol messages into `tx` until the connection closes
+async fn read_messages(reader: OwnedReadHalf, tx: mpsc::UnboundedSender<Message>) -> Result<()> {
+    let mut lines = BufReader::new(reader).lines();
+    let mut current: Option<Message> = None;
+
+    while let Some(line) = lines.next_line().await? {
+        if line.len() < 4 || !line.is_char_boundary(4) {
+            bail!("Malformed control port line: {}", line);
+        }
+        let code = line[..3].parse().with_context(|| format!("Malformed control port line: {}", line))?;
+        let message = current.get_or_insert_with(|| Message { code, text: line[4..].to_string(), data: None });
+
+        match &line[3..4] {
+            " " => {
+                let complete = current.take().expect("set above");
+                if tx.send(complete).is_err() {
+                    return Ok(());
+                }
+            }
+            "+" => {
+                let mut data = String::new();
+                while let Some(line) = lines.next_line().await? {
+                    if line == "." {
+                        break;
+                    }
+                    data.push_str(line.strip_prefix('.').unwrap_or(&line));
+                    data.push('\n');
+                }
+                message.data.get_or_insert(data);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// An authenticated control port connection
+pub struct ControlConnection {
+    writer: OwnedWriteHalf,
+    messages: mpsc::UnboundedReceiver<Message>,
+    /// Events that arrived while waiting for a command's reply
+    pending: Vec<Message>,
+}
+
+/// Outcome of an `HSFETCH`
+struct DescriptorFetch {
+    hsdirs_queried: usize,
+    /// The descriptor, if an HSDir returned it
+    content: Option<String>,
+    /// Why no descriptor was returned
+    failure: String,
+}
+
+impl ControlConnection {
+    pub async fn connect(config: &ControlConfig) -> Result<Self> {
+        let stream = timeout(Duration::from_secs(10), TcpStream::connect(&config.address))
+            .await
+            .map_err(|_| anyhow!("Timed out connecting to the control port {}", config.address))?
+            .with_context(|| format!("Failed to connect to the control port {}", config.address))?;
+        let (reader, writer) = stream.into_split();
+        let (tx, messages) = mpsc::unbounded_channel();
+        tokio::spawn(read_messages(reader, tx));
+
+        let mut connection = ControlConnection { writer, messages, pending: Vec::new() };
+        let command = match &config.auth {
+            ControlAuth::None => "AUTHENTICATE".to_string(),
+            ControlAuth::Password(password) => format!("AUTHENTICATE {}", quote(password)),
+            ControlAuth::Cookie(path) => {
+                let cookie = tokio::fs::read(path).await
+                    .with_context(|| format!("Failed to read the control cookie {}", path.display()))?;
+                format!("AUTHENTICATE {}", HEXUPPER.encode(&cookie))
+            }
+        };
+        connection.command(&command).await.context("Control port authentication failed")?;
+        Ok(connection)
+    }
+
+    /// Send `command` and wait for its reply, keeping events that arrive meanwhile
+    async fn command(&mut self, command: &str) -> Result<Message> {
+        self.writer.write_all(format!("{}\r\n", command).as_bytes()).await?;
+
+        loop {
+            let message = timeout(Duration::from_secs(30), self.messages.recv())
+                .await
+                .map_err(|_| anyhow!("Timed out waiting for a reply to {}", verb(command)))?
+                .ok_or_else(|| anyhow!("Control port closed the connection"))?;
+            match message.code {
+                650 => self.pending.push(message),
+                200..=299 => return Ok(message),
+                code => bail!("{} failed: {} {}", verb(command), code, message.text),
+            }
+        }
+    }
+
+    /// Ask the HSDirs for the descriptor of `service_id`; `HS_DESC` and `HS_DESC_CONTENT`
+    /// events must be enabled. Waits until one returns it or every queried HSDir failed.
+    async fn fetch_descriptor(&mut self, service_id: &str) -> Result<DescriptorFetch> {
+        self.command(&format!("HSFETCH {}", service_id)).await?;
+
+        let mut fetch = DescriptorFetch {
+            hsdirs_queried: 0,
+            content: None,
+            failure: "No HSDir returned the descriptor".to_string(),
+        };
+        let mut failed = 0;
+        let deadline = Instant::now() + DESCRIPTOR_TIMEOUT;
+        while let Some(event) = self.next_event(deadline).await {
+            // HS_DESC <action> <address> ...; HS_DESC_CONTENT <address> ...
+            let fields: Vec<&str> = event.text.split(' ').collect();
+            let (action, address) = match fields[0] {
+                "HS_DESC" => (fields.get(1).copied(), fields.get(2).copied()),
+                _ => (None, fields.get(1).copied()),
+            };
+            if address != Some(service_id) {
+                continue;
+            }
+            match (fields[0], action) {
+                ("HS_DESC", Some("REQUESTED")) => fetch.hsdirs_queried += 1,
+                ("HS_DESC", Some("FAILED")) => {
+                    failed += 1;
+                    if let Some(reason) = fields.iter().find_map(|f| f.strip_prefix("REASON=")) {
+                        fetch.failure = format!("HSDir lookup failed: {}", reason);
+                    }
+                }
+                ("HS_DESC_CONTENT", _) => {
+                    fetch.content = event.data.filter(|d| !d.trim().is_empty());
+                    if fetch.content.is_some() {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+            if fetch.hsdirs_queried > 0 && failed >= fetch.hsdirs_queried {
+                break;
+            }
+        }
+        Ok(fetch)
+    }
+
+    /// Next asynchronous event, or `None` once `deadline` passes
+    async fn next_event(&mut self, deadline: Instant) -> Option<Message> {
+        if !self.pending.is_empty() {
+            return Some(self.pending.remove(0));
+        }
+        loop {
+            let message = tokio::time::timeout_at(deadline, self.messages.recv()).await.ok()??;
+            if message.code == 650 {
+                return Some(message);
+            }
+        }
+    }
+}
+
+fn verb(command: &str) -> &str {
+    command.split(' ').next().unwrap_or(command)
+}
+
+/// Quote a string argument as the control protocol expects
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Hand the client authorization keys to Tor for this session only (they are not saved
+/// to Tor's `ClientOnionAuthDir`), so that it can connect to restricted services
+pub async fn add_client_auth(config: &ControlConfig, keys: &ClientAuthKeys) -> Result<()> {
+    let mut control = ControlConnection::connect(config).await?;
+    for key in keys.iter() {
+        control.command(&format!("ONION_CLIENT_AUTH_ADD {} {}", key.service_id, key.control_argument()))
+            .await
+            .with_context(|| format!("Failed to add the client auth key for {}.onion", key.service_id))?;
+    }
+    Ok(())
+}
+
+/// Fetch and decrypt the current descriptor of `onion_url` from the HSDirs
+pub async fn fetch_descriptor(
+    config: &ControlConfig,
+    onion_url: &str,
+    client_key: Option<&[u8; 32]>,
+) -> Result<descriptor::Descriptor> {
+    let identity_key = descriptor::identity_key(onion_url)?;
+    let service_id = descriptor::service_id(onion_url)?;
+
+    let mut control = ControlConnection::connect(config).await?;
+    control.command("SETEVENTS HS_DESC HS_DESC_CONTENT").await?;
+    let fetch = control.fetch_descriptor(&service_id).await?;
+    let content = fetch.content.ok_or_else(|| anyhow!(fetch.failure))?;
+    descriptor::parse(&content, &identity_key, client_key).context("Unreadable descriptor")
+}
+
+/// Work out where connecting to `onion_url` fails: fetch its descriptor, then try to
+/// connect through the SOCKS proxy while watching the onion service circuits
+pub async fn diagnose(
+    config: &ControlConfig,
+    onion_url: &str,
+    client_key: Option<&[u8; 32]>,
+    proxy: &SocksProxy,
+) -> Diagnosis {
+    let mut diagnosis = Diagnosis {
+        failed_at: FailureStage::Unknown,
+        descriptor_found: false,
+        hsdirs_queried: 0,
+        descriptor_revision: None,
+        intro_points: None,
+        circuits: CircuitSummary::default(),
+        details: None,
+        checked_at: Utc::now(),
+    };
+
+    if let Err(e) = run_diagnosis(&mut diagnosis, config, onion_url, client_key, proxy).await {
+        diagnosis.failed_at = FailureStage::Unknown;
+        diagnosis.details = Some(format!("{:#}", e));
+    }
+    diagnosis
+}
+
+async fn run_diagnosis(
+    diagnosis: &mut Diagnosis,
+    config: &ControlConfig,
+    onion_url: &str,
+    client_key: Option<&[u8; 32]>,
+    proxy: &SocksProxy,
+) -> Result<()> {
+    let identity_key = descriptor::identity_key(onion_url)?;
+    let service_id = descriptor::service_id(onion_url)?;
+    let (host, port) = host_and_port(onion_url);
+
+    let mut control = ControlConnection::connect(config).await?;
+    control.command("SETEVENTS HS_DESC HS_DESC_CONTENT CIRC").await?;
+    let fetch = control.fetch_descriptor(&service_id).await?;
+    diagnosis.hsdirs_queried = fetch.hsdirs_queried;
+
+    let Some(content) = fetch.content else {
+        diagnosis.failed_at = FailureStage::Descriptor;
+        diagnosis.details = Some(fetch.failure);
+        return Ok(());
+    };
+    diagnosis.descriptor_found = true;
+
+    match descriptor::parse(&content, &identity_key, client_key) {
+        Ok(parsed) => {
+            diagnosis.descriptor_revision = Some(parsed.revision_counter);
+            diagnosis.intro_points = parsed.intro_points;
+        }
+        Err(e) => {
+            diagnosis.failed_at = FailureStage::Descriptor;
+            diagnosis.details = Some(format!("Unreadable descriptor: {:#}", e));
+            return Ok(());
+        }
+    }
+    if diagnosis.intro_points == Some(0) {
+        diagnosis.failed_at = FailureStage::Introduction;
+        diagnosis.details = Some("The descriptor lists no introduction points".to_string());
+        return Ok(());
+    }
+
+    // Connect through the proxy and watch the circuits Tor builds for it
+    let probe = timeout(PROBE_TIMEOUT, proxy.connect(&host, port));
+    tokio::pin!(probe);
+    let probe_result = loop {
+        tokio::select! {
+            result = &mut probe => break result,
+            Some(event) = control.next_event(Instant::now() + PROBE_TIMEOUT) => {
+                record_circuit(&mut diagnosis.circuits, &event, &service_id);
+            }
+        }
+    };
+    let grace = Instant::now() + EVENT_GRACE;
+    while let Some(event) = control.next_event(grace).await {
+        record_circuit(&mut diagnosis.circuits, &event, &service_id);
+    }
+
+    let circuits = &diagnosis.circuits;
+    diagnosis.failed_at = match probe_result {
+        Ok(Ok(_)) => FailureStage::Service,
+        _ if circuits.rend_joined > 0 => FailureStage::Service,
+        _ if circuits.intro_succeeded > 0 => FailureStage::Rendezvous,
+        _ if circuits.intro_failed > 0 => FailureStage::Introduction,
+        _ if circuits.rend_failed > 0 => FailureStage::Rendezvous,
+        _ => FailureStage::Circuit,
+    };
+    diagnosis.details = match probe_result {
+        Ok(Ok(_)) => Some("Tor connected to the service".to_string()),
+        Ok(Err(e)) => Some(format!("SOCKS connection failed: {}", e)),
+        Err(_) => Some("SOCKS connection timed out".to_string()),
+    };
+
+    Ok(())
+}
+
+/// Count an onion service `CIRC` event for `service_id`
+fn record_circuit(summary: &mut CircuitSummary, event: &Message, service_id: &str) {
+    let fields: Vec<&str> = event.text.split(' ').collect();
+    if fields.first() != Some(&"CIRC") {
+        return;
+    }
+    let value = |key: &str| fields.iter().find_map(|f| f.strip_prefix(key)?.strip_prefix('='));
+    if value("REND_QUERY") != Some(service_id) {
+        return;
+    }
+
+    let status = fields.get(2).copied().unwrap_or_default();
+    match (value("PURPOSE"), value("HS_STATE"), status) {
+        (Some("HS_CLIENT_INTRO"), Some("HSCI_DONE"), _) => summary.intro_succeeded += 1,
+        (Some("HS_CLIENT_INTRO"), _, "FAILED") => summary.intro_failed += 1,
+        (Some("HS_CLIENT_REND"), Some("HSCR_JOINED"), _) => summary.rend_joined += 1,
+        (Some("HS_CLIENT_REND"), _, "FAILED") => summary.rend_failed += 1,
+        _ => {}
+    }
+}
+
+fn host_and_port(url: &str) -> (String, u16) {
+    let (default_port, rest) = match url.trim().split_once("://") {
+        Some(("https", rest)) => (443, rest),
+        Some((_, rest)) => (80, rest),
+        None => (80, url.trim()),
+    };
+    let authority = rest.split('/').next().unwrap_or_default();
+    match authority.rsplit_once(':') {
+        Some((host, port)) => (host.to_string(), port.parse().unwrap_or(default_port)),
+        None => (authority.to_string(), default_port),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::descriptor::testing;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    /// A control port that answers every command with `250 OK` and replies to
+    /// `HSFETCH` with the given events
+    async fn mock_control_port(events: Vec<String>) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let handle = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            let mut commands = Vec::new();
+            while let Ok(Some(line)) = lines.next_line().await {
+                writer.write_all(b"250 OK\r\n").await.unwrap();
+                if line.starts_with("HSFETCH") {
+                    for event in &events {
+                        writer.write_all(event.as_bytes()).await.unwrap();
+                    }
+                }
+                commands.push(line);
+            }
+            commands
+        });
+
+        (address, handle)
+    }
+
+    /// A SOCKS proxy that refuses every connection
+    async fn refusing_proxy() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut greeting = [0u8; 3];
+                let _ = stream.read_exact(&mut greeting).await;
+                let _ = stream.write_all(&[5, 0]).await;
+                let mut request = [0u8; 512];
+                let _ = stream.read(&mut request).await;
+                // General failure
+                let _ = stream.write_all(&[5, 1, 0, 1, 0, 0, 0, 0, 0, 0]).await;
+            }
+        });
+        port
+    }
+
+    fn proxy(address: &str) -> SocksProxy {
+        SocksProxy::parse(&format!("socks5h://{}", address)).unwrap()
+    }
+
+    fn config(address: String) -> ControlConfig {
+        ControlConfig { address, auth: ControlAuth::Password("secret \"pass\"".to_string()) }
+    }
+
+    #[tokio::test]
+    async fn reports_missing_descriptors() {
+        let onion = testing::onion_address();
+        let id = onion.trim_end_matches(".onion");
+        let events = vec![
+            format!("650 HS_DESC REQUESTED {} NO_AUTH $AAAA descid\r\n", id),
+            format!("650 HS_DESC REQUESTED {} NO_AUTH $BBBB descid\r\n", id),
+            format!("650 HS_DESC FAILED {} NO_AUTH $AAAA descid REASON=NOT_FOUND\r\n", id),
+            format!("650 HS_DESC FAILED {} NO_AUTH $BBBB descid REASON=NOT_FOUND\r\n", id),
+        ];
+        let (address, control) = mock_control_port(events).await;
+
+        let diagnosis = diagnose(&config(address), &format!("http://{}", onion), None, &proxy("127.0.0.1:1")).await;
+        assert_eq!(diagnosis.failed_at, FailureStage::Descriptor, "{:?}", diagnosis.details);
+        assert!(!diagnosis.descriptor_found);
+        assert_eq!(diagnosis.hsdirs_queried, 2);
+        assert_eq!(diagnosis.details.as_deref(), Some("HSDir lookup failed: NOT_FOUND"));
+
+        let commands = control.await.unwrap();
+        assert_eq!(commands[0], "AUTHENTICATE \"secret \\\"pass\\\"\"");
+        assert_eq!(commands[2], format!("HSFETCH {}", id));
+    }
+
+    #[tokio::test]
+    async fn reports_failed_introductions() {
+        let onion = testing::onion_address();
+        let id = onion.trim_end_matches(".onion");
+        let descriptor = testing::descriptor(7, Utc::now(), 3, None).replace("\n", "\r\n");
+        let events = vec![
+            format!("650 HS_DESC REQUESTED {} NO_AUTH $AAAA descid\r\n", id),
+            format!("650 HS_DESC RECEIVED {} NO_AUTH $AAAA descid\r\n", id),
+            format!("650+HS_DESC_CONTENT {} descid $AAAA\r\n{}.\r\n650 OK\r\n", id, descriptor),
+            format!("650 CIRC 5 LAUNCHED PURPOSE=HS_CLIENT_INTRO HS_STATE=HSCI_CONNECTING REND_QUERY={}\r\n", id),
+            format!("650 CIRC 5 FAILED $A~a PURPOSE=HS_CLIENT_INTRO HS_STATE=HSCI_CONNECTING REND_QUERY={} REASON=TIMEOUT\r\n", id),
+            format!("650 CIRC 6 FAILED $B~b PURPOSE=HS_CLIENT_INTRO HS_STATE=HSCI_CONNECTING REND_QUERY={} REASON=TIMEOUT\r\n", id),
+            "650 CIRC 7 FAILED $C~c PURPOSE=HS_CLIENT_INTRO REND_QUERY=someoneelse REASON=TIMEOUT\r\n".to_string(),
+        ];
+        let (address, _control) = mock_control_port(events).await;
+        let proxy_port = refusing_proxy().await;
+
+        let diagnosis = diagnose(&config(address), &format!("http://{}", onion), None, &proxy(&format!("127.0.0.1:{}", proxy_port))).await;
+        assert_eq!(diagnosis.failed_at, FailureStage::Introduction, "{:?}", diagnosis.details);
+        assert!(diagnosis.descriptor_found);
+        assert_eq!(diagnosis.descriptor_revision, Some(7));
+        assert_eq!(diagnosis.intro_points, Some(3));
+        assert_eq!(diagnosis.circuits.intro_failed, 2);
+        assert_eq!(diagnosis.circuits.rend_joined, 0);
+    }
+
+    #[tokio::test]
+    async fn fetches_and_decrypts_descriptors() {
+        let onion = testing::onion_address();
+        let id = onion.trim_end_matches(".onion");
+        let descriptor = testing::descriptor(11, Utc::now(), 2, Some([1; 32])).replace("\n", "\r\n");
+        let events = vec![
+            "650 HS_DESC REQUESTED someoneelse NO_AUTH $CCCC descid\r\n".to_string(),
+            format!("650 HS_DESC REQUESTED {} NO_AUTH $AAAA descid\r\n", id),
+            format!("650+HS_DESC_CONTENT {} descid $AAAA\r\n{}.\r\n650 OK\r\n", id, descriptor),
+        ];
+        let (address, control) = mock_control_port(events).await;
+
+        let url = format!("https://www.{}/", onion);
+        let parsed = fetch_descriptor(&config(address), &url, Some(&[1; 32])).await.unwrap();
+        assert_eq!(parsed.revision_counter, 11);
+        assert_eq!(parsed.intro_points, Some(2));
+
+        let commands = control.await.unwrap();
+        assert_eq!(commands[1], "SETEVENTS HS_DESC HS_DESC_CONTENT");
+        assert_eq!(commands[2], format!("HSFETCH {}", id));
+    }
+
+    #[test]
+    fn splits_host_and_port() {
+        assert_eq!(host_and_port("http://abc.onion"), ("abc.onion".to_string(), 80));
+        assert_eq!(host_and_port("https://abc.onion/x"), ("abc.onion".to_string(), 443));
+        assert_eq!(host_and_port("http://abc.onion:8080/"), ("abc.onion".to_string(), 8080));
+    }
+}
@@ -0,0 +1,426 @@
+use aes::Aes256;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use ctr::cipher::{KeyIvInit, StreamCipher};
+use data_encoding::{BASE32_NOPAD, BASE64, BASE64_NOPAD};
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::{Digest, Sha3_256, Shake256};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+type Aes256Ctr = ctr::Ctr128BE<Aes256>;
+
+const SALT_LEN: usize = 16;
+const MAC_LEN: usize = 32;
+const KEY_LEN: usize = 32;
+const IV_LEN: usize = 16;
+const CLIENT_ID_LEN: usize = 8;
+
+/// Cert extension holding the key that signed the certificate (here, the blinded key)
+const SIGNED_WITH_ED25519_KEY: u8 = 0x04;
+
+/// What a v3 onion service descriptor (rend-spec-v3) says about the service
+#[derive(Debug, Clone)]
+pub struct Descriptor {
+    pub revision_counter: u64,
+    /// How long HSDirs keep the descriptor, in minutes
+    pub lifetime_minutes: u32,
+    /// When the descriptor signing key certificate expires
+    pub signing_key_expires: DateTime<Utc>,
+    /// `None` when the inner layer needs a client authorization key we do not have
+    pub intro_points: Option<usize>,
+}
+
+/// Public identity key of a v3 onion address (with or without scheme, port and `.onion`)
+pub fn identity_key(address: &str) -> Result<[u8; 32]> {
+    let label = service_label(address);
+    let decoded = BASE32_NOPAD.decode(label.to_ascii_uppercase().as_bytes())
+        .map_err(|_| anyhow!("{} is not a v3 onion address", address))?;
+    let [key @ .., c0, c1, version] = decoded.as_slice() else {
+        bail!("{} is not a v3 onion address", address);
+    };
+    if key.len() != 32 || *version != 3 {
+        bail!("{} is not a v3 onion address", address);
+    }
+
+    let checksum = Sha3_256::new()
+        .chain_update(b".onion checksum")
+        .chain_update(key)
+        .chain_update([*version])
+        .finalize();
+    if checksum[..2] != [*c0, *c1] {
+        bail!("{} has an invalid checksum", address);
+    }
+
+    Ok(key.try_into().expect("length checked above"))
+}
+
+/// Service ID of a v3 onion address: the 56 characters before `.onion`, as the control port expects
+pub fn service_id(address: &str) -> Result<String> {
+    identity_key(address)?;
+    Ok(service_label(address).to_ascii_lowercase())
+}
+
+fn service_label(address: &str) -> &str {
+    let host = address.trim()
+        .trim_start_matches("http://")
+        .trim_start_matches("https://")
+        .split(['/', ':'])
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches(".onion");
+    // Subdomains of an onion address belong to the same service
+    host.rsplit('.').next().unwrap_or_default()
+}
+
+/// Parse a descriptor as returned in `HS_DESC_CONTENT`, decrypting both layers. The inner
+/// layer of a service with client authorization can only be read with the x25519 `client_key`.
+pub fn parse(text: &str, identity_key: &[u8; 32], client_key: Option<&[u8; 32]>) -> Result<Descriptor> {
+    let version = field(text, "hs-descriptor").context("Missing hs-descriptor")?;
+    if version != "3" {
+        bail!("Unsupported descriptor version {}", version);
+    }
+    let revision_counter = field(text, "revision-counter")
+        .and_then(|v| v.parse().ok())
+        .context("Missing revision-counter")?;
+    let lifetime_minutes = field(text, "descriptor-lifetime")
+        .and_then(|v| v.parse().ok())
+        .context("Missing descriptor-lifetime")?;
+
+    let cert = decode_block(text, "descriptor-signing-key-cert", "ED25519 CERT")?;
+    let blinded_key = blinded_key(&cert)?;
+    // Checked by blinded_key: the expiration follows the version and type bytes, in hours
+    let expires_hours = u32::from_be_bytes(cert[2..6].try_into().expect("length checked"));
+    let signing_key_expires = DateTime::from_timestamp(i64::from(expires_hours) * 3600, 0)
+        .context("Invalid descriptor-signing-key-cert expiration")?;
+
+    let subcredential = subcredential(identity_key, &blinded_key);
+    let superencrypted = decode_block(text, "superencrypted", "MESSAGE")?;
+    let middle = decrypt_layer(&superencrypted, &blinded_key, &subcredential, revision_counter, b"hsdir-superencrypted-data")
+        .context("Failed to decrypt the outer layer")?;
+    let middle = String::from_utf8_lossy(&middle);
+
+    // The inner layer uses a descriptor cookie when client authorization is enabled
+    let encrypted = decode_block(&middle, "encrypted", "MESSAGE")?;
+    let mut secret_data = blinded_key.to_vec();
+    if let Some(cookie) = client_key.and_then(|key| descriptor_cookie(&middle, key, &subcredential)) {
+        secret_data.extend_from_slice(&cookie);
+    }
+    let intro_points = decrypt_layer(&encrypted, &secret_data, &subcredential, revision_counter, b"hsdir-encrypted-data")
+        .ok()
+        .map(|inner| {
+            String::from_utf8_lossy(&inner)
+                .lines()
+                .filter(|line| line.starts_with("introduction-point "))
+                .count()
+        });
+
+    Ok(Descriptor { revision_counter, lifetime_minutes, signing_key_expires, intro_points })
+}
+
+/// Value of the first `keyword` line
+fn field<'a>(text: &'a str, keyword: &str) -> Option<&'a str> {
+    text.lines().find_map(|line| {
+        let rest = line.strip_prefix(keyword)?;
+        rest.strip_prefix(' ').or(rest.is_empty().then_some(""))
+    })
+}
+
+/// Body of the `-----BEGIN <label>-----` block that follows the `keyword` line
+fn block<'a>(text: &'a str, keyword: &str, label: &str) -> Result<&'a str> {
+    let begin = format!("-----BEGIN {}-----", label);
+    let end = format!("-----END {}-----", label);
+
+    let start = text.lines()
+        .scan(0, |offset, line| {
+            let line_start = *offset;
+            *offset += line.len() + 1;
+            Some((line_start, line))
+        })
+        .find(|(_, line)| line.trim_end() == keyword)
+        .map(|(offset, line)| offset + line.len() + 1)
+        .ok_or_else(|| anyhow!("Missing {}", keyword))?;
+
+    let rest = text.get(start..).unwrap_or_default().trim_start();
+    let body = rest.strip_prefix(begin.as_str()).ok_or_else(|| anyhow!("Malformed {}", keyword))?;
+    let body_end = body.find(end.as_str()).ok_or_else(|| anyhow!("Unterminated {}", keyword))?;
+    Ok(&body[..body_end])
+}
+
+/// Decoded base64 body of the block following the `keyword` line
+fn decode_block(text: &str, keyword: &str, label: &str) -> Result<Vec<u8>> {
+    let body: String = block(text, keyword, label)?.chars().filter(|c| !c.is_whitespace()).collect();
+    BASE64.decode(body.as_bytes()).with_context(|| format!("Invalid {}", keyword))
+}
+
+/// Recover the descriptor cookie from the `auth-client` line meant for `client_key`, if any
+fn descriptor_cookie(middle: &str, client_key: &[u8; 32], subcredential: &[u8; 32]) -> Option<[u8; 32]> {
+    let ephemeral_key: [u8; 32] = decode_base64(field(middle, "desc-auth-ephemeral-key")?)?.try_into().ok()?;
+    let shared = StaticSecret::from(*client_key).diffie_hellman(&PublicKey::from(ephemeral_key));
+
+    let mut keys = [0u8; CLIENT_ID_LEN + KEY_LEN];
+    let mut shake = Shake256::default();
+    shake.update(subcredential);
+    shake.update(shared.as_bytes());
+    shake.finalize_xof().read(&mut keys);
+    let (client_id, cookie_key) = keys.split_at(CLIENT_ID_LEN);
+
+    // Services add fake entries, so only the one with our client ID decrypts
+    middle.lines()
+        .filter_map(|line| line.strip_prefix("auth-client "))
+        .find_map(|entry| {
+            let [id, iv, encrypted_cookie] = entry.split(' ').collect::<Vec<_>>()[..] else {
+                return None;
+            };
+            if decode_base64(id)? != client_id {
+                return None;
+            }
+            let iv: [u8; IV_LEN] = decode_base64(iv)?.try_into().ok()?;
+            let mut cookie: [u8; 32] = decode_base64(encrypted_cookie)?.try_into().ok()?;
+            Aes256Ctr::new(cookie_key.into(), &iv.into()).apply_keystream(&mut cookie);
+            Some(cookie)
+        })
+}
+
+/// Descriptor fields are base64 with the padding stripped
+fn decode_base64(value: &str) -> Option<Vec<u8>> {
+    BASE64_NOPAD.decode(value.trim().trim_end_matches('=').as_bytes()).ok()
+}
+
+/// Key that signed an Ed25519 certificate (tor cert-spec)
+fn blinded_key(cert: &[u8]) -> Result<[u8; 32]> {
+    let malformed = || anyhow!("Malformed descriptor-signing-key-cert");
+    if cert.len() < 40 || cert[0] != 1 {
+        return Err(malformed());
+    }
+
+    let mut offset = 40;
+    for _ in 0..cert[39] {
+        let header = cert.get(offset..offset + 4).ok_or_else(malformed)?;
+        let len = usize::from(u16::from_be_bytes([header[0], header[1]]));
+        let data = cert.get(offset + 4..offset + 4 + len).ok_or_else(malformed)?;
+        if header[2] == SIGNED_WITH_ED25519_KEY && len == 32 {
+            return Ok(data.try_into().expect("length checked"));
+        }
+        offset += 4 + len;
+    }
+
+    bail!("descriptor-signing-key-cert does not include the blinded key")
+}
+
+fn subcredential(identity_key: &[u8; 32], blinded_key: &[u8; 32]) -> [u8; 32] {
+    let credential = Sha3_256::new()
+        .chain_update(b"credential")
+        .chain_update(identity_key)
+        .finalize();
+    Sha3_256::new()
+        .chain_update(b"subcredential")
+        .chain_update(credential)
+        .chain_update(blinded_key)
+        .finalize()
+        .into()
+}
+
+/// Decrypt one descriptor layer: `SALT | ENCRYPTED | MAC`, keyed by SHAKE-256 over the secret input
+fn decrypt_layer(
+    blob: &[u8],
+    secret_data: &[u8],
+    subcredential: &[u8; 32],
+    revision_counter: u64,
+    constant: &[u8],
+) -> Result<Vec<u8>> {
+    if blob.len() < SALT_LEN + MAC_LEN {
+        bail!("Encrypted layer is too short");
+    }
+    let (salt, rest) = blob.split_at(SALT_LEN);
+    let (ciphertext, mac) = rest.split_at(rest.len() - MAC_LEN);
+
+    let mut keys = [0u8; KEY_LEN + IV_LEN + MAC_LEN];
+    let mut shake = Shake256::default();
+    shake.update(secret_data);
+    shake.update(subcredential);
+    shake.update(&revision_counter.to_be_bytes());
+    shake.update(salt);
+    shake.update(constant);
+    shake.finalize_xof().read(&mut keys);
+    let (key, rest) = keys.split_at(KEY_LEN);
+    let (iv, mac_key) = rest.split_at(IV_LEN);
+
+    let expected = Sha3_256::new()
+        .chain_update((MAC_LEN as u64).to_be_bytes())
+        .chain_update(mac_key)
+        .chain_update((SALT_LEN as u64).to_be_bytes())
+        .chain_update(salt)
+        .chain_update(ciphertext)
+        .finalize();
+    if expected.as_slice() != mac {
+        bail!("MAC mismatch");
+    }
+
+    let mut plaintext = ciphertext.to_vec();
+    Aes256Ctr::new(key.into(), iv.into()).apply_keystream(&mut plaintext);
+    // Layers are padded with NULs
+    while plaintext.last() == Some(&0) {
+        plaintext.pop();
+    }
+    Ok(plaintext)
+}
+
+/// Build descriptors the way an onion service would, for tests
+#[cfg(test)]
+pub mod testing {
+    use super::*;
+
+    pub const IDENTITY_KEY: [u8; 32] = [7; 32];
+    pub const BLINDED_KEY: [u8; 32] = [9; 32];
+
+    pub fn onion_address() -> String {
+        onion_address_of(&IDENTITY_KEY)
+    }
+
+    pub fn onion_address_of(identity_key: &[u8; 32]) -> String {
+        let checksum = Sha3_256::new()
+            .chain_update(b".onion checksum")
+            .chain_update(identity_key)
+            .chain_update([3])
+            .finalize();
+        let mut bytes = identity_key.to_vec();
+        bytes.extend_from_slice(&checksum[..2]);
+        bytes.push(3);
+        format!("{}.onion", BASE32_NOPAD.encode(&bytes).to_ascii_lowercase())
+    }
+
+    fn encrypt_layer(plaintext: &[u8], secret_data: &[u8], revision_counter: u64, constant: &[u8]) -> Vec<u8> {
+        let salt = [5u8; SALT_LEN];
+        let subcredential = subcredential(&IDENTITY_KEY, &BLINDED_KEY);
+        let mut keys = [0u8; KEY_LEN + IV_LEN + MAC_LEN];
+        let mut shake = Shake256::default();
+        shake.update(secret_data);
+        shake.update(&subcredential);
+        shake.update(&revision_counter.to_be_bytes());
+        shake.update(&salt);
+        shake.update(constant);
+        shake.finalize_xof().read(&mut keys);
+
+        let mut ciphertext = plaintext.to_vec();
+        ciphertext.resize(plaintext.len().div_ceil(100) * 100, 0);
+        Aes256Ctr::new(keys[..KEY_LEN].into(), keys[KEY_LEN..KEY_LEN + IV_LEN].into()).apply_keystream(&mut ciphertext);
+        let mac = Sha3_256::new()
+            .chain_update((MAC_LEN as u64).to_be_bytes())
+            .chain_update(&keys[KEY_LEN + IV_LEN..])
+            .chain_update((SALT_LEN as u64).to_be_bytes())
+            .chain_update(salt)
+            .chain_update(&ciphertext)
+            .finalize();
+
+        [salt.as_slice(), &ciphertext, &mac].concat()
+    }
+
+    fn armor(label: &str, bytes: &[u8]) -> String {
+        let encoded = BASE64.encode(bytes);
+        let lines: Vec<&str> = encoded.as_bytes().chunks(64).map(|c| std::str::from_utf8(c).unwrap()).collect();
+        format!("-----BEGIN {label}-----\n{}\n-----END {label}-----", lines.join("\n"))
+    }
+
+    /// A descriptor listing `intro_points` introduction points; `client_key` enables client
+    /// authorization for the client with that x25519 secret key
+    pub fn descriptor(revision_counter: u64, expires: DateTime<Utc>, intro_points: usize, client_key: Option<[u8; 32]>) -> String {
+        let mut cert = vec![1, 8];
+        cert.extend_from_slice(&((expires.timestamp() / 3600) as u32).to_be_bytes());
+        cert.push(1);
+        cert.extend_from_slice(&[3; 32]);
+        cert.push(1);
+        cert.extend_from_slice(&32u16.to_be_bytes());
+        cert.extend_from_slice(&[SIGNED_WITH_ED25519_KEY, 0]);
+        cert.extend_from_slice(&BLINDED_KEY);
+        cert.extend_from_slice(&[0; 64]);
+
+        let inner: String = std::iter::once("create2-formats 2\n".to_string())
+            .chain((0..intro_points).map(|i| format!("introduction-point AQAGfwAAAQ{}\nonion-key ntor AAAA\n", i)))
+            .collect();
+
+        let ephemeral = StaticSecret::from([6; 32]);
+        let mut auth_clients = "auth-client AAAAAAAAAAA AAAAAAAAAAAAAAAAAAAAAA AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA\n".to_string();
+        let mut secret = BLINDED_KEY.to_vec();
+        if let Some(client_key) = client_key {
+            let cookie = [4u8; 32];
+            let shared = ephemeral.diffie_hellman(&PublicKey::from(&StaticSecret::from(client_key)));
+            let mut keys = [0u8; CLIENT_ID_LEN + KEY_LEN];
+            let mut shake = Shake256::default();
+            shake.update(&subcredential(&IDENTITY_KEY, &BLINDED_KEY));
+            shake.update(shared.as_bytes());
+            shake.finalize_xof().read(&mut keys);
+
+            let iv = [2u8; IV_LEN];
+            let mut encrypted_cookie = cookie;
+            Aes256Ctr::new(keys[CLIENT_ID_LEN..].into(), &iv.into()).apply_keystream(&mut encrypted_cookie);
+            auth_clients.push_str(&format!(
+                "auth-client {} {} {}\n",
+                BASE64_NOPAD.encode(&keys[..CLIENT_ID_LEN]),
+                BASE64_NOPAD.encode(&iv),
+                BASE64_NOPAD.encode(&encrypted_cookie),
+            ));
+            secret.extend_from_slice(&cookie);
+        }
+        let encrypted = encrypt_layer(inner.as_bytes(), &secret, revision_counter, b"hsdir-encrypted-data");
+        let middle = format!(
+            "desc-auth-type x25519\ndesc-auth-ephemeral-key {}\n{}encrypted\n{}\n",
+            BASE64.encode(PublicKey::from(&ephemeral).as_bytes()),
+            auth_clients,
+            armor("MESSAGE", &encrypted),
+        );
+        let superencrypted = encrypt_layer(middle.as_bytes(), &BLINDED_KEY, revision_counter, b"hsdir-superencrypted-data");
+
+        format!(
+            "hs-descriptor 3\ndescriptor-lifetime 180\ndescriptor-signing-key-cert\n{}\nrevision-counter {}\nsuperencrypted\n{}\nsignature AAAA\n",
+            armor("ED25519 CERT", &cert),
+            revision_counter,
+            armor("MESSAGE", &superencrypted),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::*;
+    use super::*;
+
+    #[test]
+    fn decodes_v3_onion_addresses() {
+        let address = onion_address();
+        assert_eq!(identity_key(&address).unwrap(), IDENTITY_KEY);
+        assert_eq!(identity_key(&format!("http://www.{}:8080/path", address)).unwrap(), IDENTITY_KEY);
+        let id = address.trim_end_matches(".onion");
+        assert_eq!(service_id(&format!("https://{}.onion/", id.to_uppercase())).unwrap(), id);
+
+        let mut corrupted = address.clone();
+        corrupted.replace_range(0..1, if address.starts_with('a') { "b" } else { "a" });
+        assert!(identity_key(&corrupted).is_err());
+        assert!(identity_key("http://example.org").is_err());
+    }
+
+    #[test]
+    fn counts_intro_points_in_the_inner_layer() {
+        let expires = DateTime::from_timestamp(1_700_002_800, 0).unwrap();
+        let text = descriptor(42, expires, 3, None);
+
+        let parsed = parse(&text, &IDENTITY_KEY, None).unwrap();
+        assert_eq!(parsed.revision_counter, 42);
+        assert_eq!(parsed.lifetime_minutes, 180);
+        assert_eq!(parsed.signing_key_expires, expires);
+        assert_eq!(parsed.intro_points, Some(3));
+    }
+
+    #[test]
+    fn restricted_descriptors_need_the_client_key() {
+        let text = descriptor(1, Utc::now(), 3, Some([1; 32]));
+        assert_eq!(parse(&text, &IDENTITY_KEY, None).unwrap().intro_points, None);
+        assert_eq!(parse(&text, &IDENTITY_KEY, Some(&[2; 32])).unwrap().intro_points, None);
+        assert_eq!(parse(&text, &IDENTITY_KEY, Some(&[1; 32])).unwrap().intro_points, Some(3));
+    }
+
+    #[test]
+    fn rejects_descriptors_for_another_service() {
+        let text = descriptor(1, Utc::now(), 3, None);
+        assert!(parse(&text, &[8; 32], None).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::OnionSite;
+
+/// Version of the `onions.json` layout written by this build.
+///
+/// 1: bare array of sites (no version field)
+/// 2: `{ schema_version, run, sites }`
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Top-level layout of `onions.json`
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SitesDocument {
+    pub schema_version: u32,
+    /// Metadata about the run that produced this file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run: Option<RunMetadata>,
+    pub sites: Vec<OnionSite>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct RunMetadata {
+    pub started_at: DateTime<Utc>,
+    /// `None` while the run is still in progress
+    pub finished_at: Option<DateTime<Utc>>,
+    /// The primary SOCKS proxy, or `arti (embedded)`
+    pub proxy: String,
+    /// Names of all vantage points, when sites were checked through several proxies
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vantages: Vec<String>,
+    pub tool_version: String,
+    pub counts: StatusCounts,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Default)]
+pub struct StatusCounts {
+    pub total: usize,
+    pub online: usize,
+    pub partial: usize,
+    pub offline: usize,
+    pub error: usize,
+    pub unknown: usize,
+}
+
+impl StatusCounts {
+    pub fn from_sites(sites: &[OnionSite]) -> Self {
+        let mut counts = StatusCounts {
+            total: sites.len(),
+            ..Default::default()
+        };
+        for site in sites {
+            match site.status.as_str() {
+                "online" => counts.online += 1,
+                "partial" => counts.partial += 1,
+                "offline" => counts.offline += 1,
+                "unknown" => counts.unknown += 1,
+                _ => counts.error += 1,
+            }
+        }
+        counts
+    }
+}
+
+impl RunMetadata {
+    pub fn new(started_at: DateTime<Utc>, proxy: &str) -> Self {
+        RunMetadata {
+            started_at,
+            finished_at: None,
+            proxy: proxy.to_string(),
+            vantages: Vec::new(),
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            counts: StatusCounts::default(),
+        }
+    }
+}
+
+impl SitesDocument {
+    pub fn new(run: RunMetadata, sites: Vec<OnionSite>) -> Self {
+        let mut run = run;
+        run.counts = StatusCounts::from_sites(&sites);
+        SitesDocument {
+            schema_version: SCHEMA_VERSION,
+            run: Some(run),
+            sites,
+        }
+    }
+
+    /// Parse any known version of `onions.json`, migrating it to the current layout
+    pub fn parse(json: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(json).context("Invalid JSON")?;
+        let mut value = migrate(value)?;
+        // Sites are parsed on their own, to put mistyped upstream fields back into `extra`
+        let sites = match value.get_mut("sites") {
+            Some(Value::Array(sites)) => std::mem::take(sites),
+            _ => Vec::new(),
+        };
+        let mut document: SitesDocument = serde_json::from_value(value).context("Invalid sites document")?;
+        document.sites = sites.into_iter()
+            .enumerate()
+            .map(|(i, site)| OnionSite::from_value(site).with_context(|| format!("Invalid site #{}", i + 1)))
+            .collect::<Result<_>>()?;
+        Ok(document)
+    }
+}
+
+fn version_of(value: &Value) -> Result<u32> {
+    match value {
+        Value::Array(_) => Ok(1),
+        Value::Object(map) => map.get("schema_version")
+            .and_then(Value::as_u64)
+            .map(|v| v as u32)
+            .context("Missing or invalid schema_version"),
+        _ => bail!("Expected an array or an object at the top level"),
+    }
+}
+
+/// Upgrade a document one version at a time until it matches `SCHEMA_VERSION`
+fn migrate(mut value: Value) -> Result<Value> {
+    let mut version = version_of(&value)?;
+    if version > SCHEMA_VERSION {
+        bail!(
+            "Document has schema_version {}, but this build only understands up to {}",
+            version, SCHEMA_VERSION
+        );
+    }
+
+    while version < SCHEMA_VERSION {
+        value = match version {
+            1 => serde_json::json!({
+                "schema_version": 2,
+                "sites": value,
+            }),
+            _ => bail!("No migration from schema_version {}", version),
+        };
+        version += 1;
+    }
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEGACY_SITE: &str = r#"{
+        "title": "Example Wallet",
+        "name": "example-wallet",
+        "onion_address": "http://wallet.onion",
+        "status": "online",
+        "prev_status": "offline",
+        "last_checked": "2024-05-01T12:00:00Z",
+        "tags": ["Wallet"]
+    }"#;
+
+    #[test]
+    fn migrates_the_legacy_array() {
+        let document = SitesDocument::parse(&format!("[{}]", LEGACY_SITE)).unwrap();
+        assert_eq!(document.schema_version, SCHEMA_VERSION);
+        assert!(document.run.is_none());
+        assert_eq!(document.sites.len(), 1);
+        assert_eq!(document.sites[0].name, "example-wallet");
+        assert_eq!(document.sites[0].status, "online");
+        assert_eq!(document.sites[0].metadata.tags, ["Wallet"]);
+
+        // Written back in the current layout
+        let run = RunMetadata::new(Utc::now(), "socks5h://127.0.0.1:9150");
+        let value = serde_json::to_value(SitesDocument::new(run, document.sites)).unwrap();
+        assert_eq!(value["schema_version"], SCHEMA_VERSION);
+        assert_eq!(value["run"]["counts"]["online"], 1);
+        assert_eq!(SitesDocument::parse(&value.to_string()).unwrap().sites.len(), 1);
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        let newer = format!(r#"{{ "schema_version": {}, "sites": [] }}"#, SCHEMA_VERSION + 1);
+        let error = SitesDocument::parse(&newer).unwrap_err().to_string();
+        assert!(error.contains("only understands up to"), "{}", error);
+
+        assert!(SitesDocument::parse(r#"{ "sites": [] }"#).is_err());
+        assert!(SitesDocument::parse(r#"{ "schema_version": "2", "sites": [] }"#).is_err());
+        assert!(SitesDocument::parse(r#""sites""#).is_err());
+        assert!(SitesDocument::parse(&format!(r#"{{ "schema_version": 2, "sites": [{}, {{}}] }}"#, LEGACY_SITE)).is_err());
+    }
+}
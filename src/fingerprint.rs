@@ -0,0 +1,276 @@
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+
+/// Fingerprint bits that may differ before a change counts as significant, unless overridden
+/// by `CONTENT_CHANGE_THRESHOLD`. Edits to a page flip a few bits, a different page about half.
+const DEFAULT_CHANGE_THRESHOLD: u32 = 10;
+/// Characters of page text kept with `CONTENT_SNAPSHOTS`
+const SNAPSHOT_CHARS: usize = 2000;
+
+/// Phrases of pages that replace a service: parking, sale and default server pages
+const PLACEHOLDERS: &[(&str, &str)] = &[
+    ("domain is for sale", "domain-for-sale page"),
+    ("buy this domain", "domain-for-sale page"),
+    ("this domain may be for sale", "domain-for-sale page"),
+    ("welcome to nginx", "default web server page"),
+    ("apache2 default page", "default web server page"),
+    ("apache2 debian default page", "default web server page"),
+    ("apache2 ubuntu default page", "default web server page"),
+    ("test page for the apache http server", "default web server page"),
+    ("account has been suspended", "hosting provider placeholder"),
+    ("this account has been suspended", "hosting provider placeholder"),
+    ("hosted by onionland", "hosting provider placeholder"),
+];
+
+/// What a service's landing page looked like at the last check
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct ContentFingerprint {
+    /// SHA3-256 of the response body
+    pub body_hash: String,
+    /// SimHash of the page text, in hex: similar pages differ in few bits
+    pub simhash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Body size in bytes
+    pub length: usize,
+    /// Kind of placeholder page the content looks like
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub placeholder: Option<String>,
+    /// Normalised page text, with `CONTENT_SNAPSHOTS`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<String>,
+    /// How the content changed significantly since the previous check
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub change: Option<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Fingerprint bits that may differ between checks, from `CONTENT_CHANGE_THRESHOLD`
+pub fn change_threshold_from_env() -> u32 {
+    match std::env::var("CONTENT_CHANGE_THRESHOLD") {
+        Ok(value) => value.trim().parse().unwrap_or_else(|_| {
+            println!("⚠ Warning: Invalid CONTENT_CHANGE_THRESHOLD '{}', using {}", value, DEFAULT_CHANGE_THRESHOLD);
+            DEFAULT_CHANGE_THRESHOLD
+        }),
+        Err(_) => DEFAULT_CHANGE_THRESHOLD,
+    }
+}
+
+/// Whether to store a text snapshot of each landing page, from `CONTENT_SNAPSHOTS`
+pub fn snapshots_from_env() -> bool {
+    matches!(std::env::var("CONTENT_SNAPSHOTS").as_deref(), Ok("1" | "true" | "yes"))
+}
+
+/// Whether the body of a final response with this status is the landing page. Error pages
+/// and redirects left unfollowed would make every outage look like a content change.
+pub fn is_landing_page(code: u16) -> bool {
+    (200..300).contains(&code)
+}
+
+/// Fingerprint after a check: of the landing page `body` if one was seen, otherwise the
+/// `previous` fingerprint, which later checks keep comparing against
+pub fn update(
+    previous: Option<&ContentFingerprint>,
+    body: Option<&[u8]>,
+    threshold: u32,
+    snapshot: bool,
+    now: DateTime<Utc>,
+) -> Option<ContentFingerprint> {
+    match body {
+        Some(body) => Some(fingerprint(body, previous, threshold, snapshot, now)),
+        None => previous.map(|content| ContentFingerprint { change: None, ..content.clone() }),
+    }
+}
+
+/// Fingerprint `body` and compare it with the `previous` check
+pub fn fingerprint(
+    body: &[u8],
+    previous: Option<&ContentFingerprint>,
+    threshold: u32,
+    snapshot: bool,
+    now: DateTime<Utc>,
+) -> ContentFingerprint {
+    let html = String::from_utf8_lossy(body);
+    let (title, text) = page_text(&html);
+    let words = words(&text);
+    let joined = words.join(" ");
+
+    let mut current = ContentFingerprint {
+        body_hash: data_encoding::HEXLOWER.encode(&Sha3_256::digest(body)),
+        simhash: format!("{:016x}", simhash(&words)),
+        title,
+        length: body.len(),
+        placeholder: PLACEHOLDERS.iter().find(|(phrase, _)| joined.contains(phrase)).map(|(_, kind)| kind.to_string()),
+        snapshot: Some(text.chars().take(SNAPSHOT_CHARS).collect()).filter(|text: &String| snapshot && !text.is_empty()),
+        change: None,
+        checked_at: now,
+    };
+    current.change = previous.and_then(|previous| compare(previous, &current, threshold));
+    current
+}
+
+/// Describe a significant change between two fingerprints
+fn compare(previous: &ContentFingerprint, current: &ContentFingerprint, threshold: u32) -> Option<String> {
+    if previous.body_hash == current.body_hash {
+        return None;
+    }
+    if let (None, Some(placeholder)) = (&previous.placeholder, &current.placeholder) {
+        return Some(format!("Landing page replaced by a {}", placeholder));
+    }
+
+    let distance = match (u64::from_str_radix(&previous.simhash, 16), u64::from_str_radix(&current.simhash, 16)) {
+        (Ok(a), Ok(b)) => (a ^ b).count_ones(),
+        _ => 0,
+    };
+    // Counters and dates in titles change all the time
+    let title_changed = previous.title.as_deref().map(without_digits) != current.title.as_deref().map(without_digits);
+    let titles = || format!("\"{}\" to \"{}\"", previous.title.as_deref().unwrap_or(""), current.title.as_deref().unwrap_or(""));
+
+    match (distance > threshold, title_changed) {
+        (true, true) => Some(format!("Content and title changed from {}", titles())),
+        (true, false) => Some(format!("Content changed ({} of 64 fingerprint bits differ)", distance)),
+        (false, true) => Some(format!("Title changed from {}", titles())),
+        (false, false) => None,
+    }
+}
+
+fn without_digits(text: &str) -> String {
+    text.chars().filter(|c| !c.is_ascii_digit()).collect()
+}
+
+/// Title and visible text of an HTML page, with whitespace collapsed
+fn page_text(html: &str) -> (Option<String>, String) {
+    // ASCII lowercasing keeps byte offsets, so indices into `lower` work on `html`
+    let lower = html.to_ascii_lowercase();
+    let title = lower.find("<title")
+        .and_then(|start| Some(start + lower[start..].find('>')? + 1))
+        .and_then(|start| Some(&html[start..start + lower[start..].find("</title")?]))
+        .map(|title| collapse(&decode_entities(title)))
+        .filter(|title| !title.is_empty());
+
+    let mut text = String::new();
+    let mut i = 0;
+    while i < html.len() {
+        let rest = &lower[i..];
+        let skip_to = if rest.starts_with("<!--") {
+            rest.find("-->").map(|end| end + 3)
+        } else if let Some(close) = rest.strip_prefix('<').and_then(|tag| ["script", "style", "title"].into_iter().find(|t| tag.starts_with(t))) {
+            // The title is kept separately
+            rest.find(&format!("</{}", close)).and_then(|end| Some(end + rest[end..].find('>')? + 1))
+        } else if rest.starts_with('<') {
+            rest.find('>').map(|end| end + 1)
+        } else {
+            let c = html[i..].chars().next().unwrap_or(' ');
+            text.push(c);
+            i += c.len_utf8();
+            continue;
+        };
+        text.push(' ');
+        i += skip_to.unwrap_or(rest.len());
+    }
+    (title, collapse(&decode_entities(&text)))
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Lowercase words, without numbers (counters, dates, block heights)
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty() && !w.chars().all(|c| c.is_ascii_digit()))
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn simhash(words: &[String]) -> u64 {
+    let mut weights = [0i64; 64];
+    for word in words {
+        let hash = fnv1a(word.as_bytes());
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            *weight += if hash >> bit & 1 == 1 { 1 } else { -1 };
+        }
+    }
+    weights.iter().enumerate().filter(|(_, w)| **w > 0).fold(0, |hash, (bit, _)| hash | 1 << bit)
+}
+
+/// FNV-1a, which unlike `DefaultHasher` is stable across Rust releases
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = "<html><head><title>Example Wallet</title><style>body { color: red }</style></head>\
+        <body><!-- build 42 --><h1>Example Wallet</h1><p>Send and receive coins privately over Tor. Auch auf Deutsch: Überweisungen ohne Konto. \
+        Your keys never leave your device, and every transaction is signed locally before it is \
+        broadcast to the network. Block height 850000.</p><script>var token = 'abc';</script></body></html>";
+
+    fn check(body: &str, previous: Option<&ContentFingerprint>) -> ContentFingerprint {
+        fingerprint(body.as_bytes(), previous, DEFAULT_CHANGE_THRESHOLD, true, Utc::now())
+    }
+
+    #[test]
+    fn extracts_title_and_text() {
+        let page = check(PAGE, None);
+        assert_eq!(page.title.as_deref(), Some("Example Wallet"));
+        let snapshot = page.snapshot.unwrap();
+        assert!(snapshot.starts_with("Example Wallet Send and receive coins"), "{}", snapshot);
+        assert!(!snapshot.contains("Wallet Example Wallet"));
+        assert!(!snapshot.contains("color") && !snapshot.contains("token") && !snapshot.contains("build"));
+        assert_eq!(page.length, PAGE.len());
+        assert_eq!(page.change, None);
+    }
+
+    #[test]
+    fn ignores_small_edits() {
+        let before = check(PAGE, None);
+        let after = check(&PAGE.replace("850000", "850001").replace("privately", "privately and quickly"), Some(&before));
+        assert_ne!(after.body_hash, before.body_hash);
+        assert_eq!(after.change, None);
+    }
+
+    #[test]
+    fn detects_replaced_content() {
+        let before = check(PAGE, None);
+
+        let defaced = "<html><head><title>Hacked</title></head><body><h1>Owned by nobody</h1>\
+            <p>Greetings to all the crews out there, see you next time.</p></body></html>";
+        let change = check(defaced, Some(&before)).change.unwrap();
+        assert!(change.starts_with("Content and title changed from \"Example Wallet\" to \"Hacked\""), "{}", change);
+
+        let parked = "<html><body><h1>This domain is for sale!</h1><p>Contact us to buy it.</p></body></html>";
+        let parked = check(parked, Some(&before));
+        assert_eq!(parked.placeholder.as_deref(), Some("domain-for-sale page"));
+        assert_eq!(parked.change.as_deref(), Some("Landing page replaced by a domain-for-sale page"));
+    }
+    #[test]
+    fn keeps_the_fingerprint_through_error_responses() {
+        let before = check(PAGE, None);
+        assert!(is_landing_page(200));
+        assert!(!is_landing_page(302) && !is_landing_page(503));
+
+        let error_page = "<html><head><title>503 Service Unavailable</title></head><body>No server is available.</body></html>";
+        let body = Some(error_page.as_bytes()).filter(|_| is_landing_page(503));
+        let during = update(Some(&before), body, DEFAULT_CHANGE_THRESHOLD, true, Utc::now()).unwrap();
+        assert_eq!(during.body_hash, before.body_hash);
+        assert_eq!(during.change, None);
+
+        let after = update(Some(&during), Some(PAGE.as_bytes()), DEFAULT_CHANGE_THRESHOLD, true, Utc::now()).unwrap();
+        assert_eq!(after.change, None);
+        assert_eq!(update(None, None, DEFAULT_CHANGE_THRESHOLD, true, Utc::now()).map(|c| c.body_hash), None);
+    }
+}
@@ -0,0 +1,189 @@
+use chrono::{DateTime, Duration, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::control::{self, ControlConfig};
+use crate::descriptor::Descriptor;
+
+/// Tor services publish three introduction points by default and replace failed ones
+const DEFAULT_MIN_INTRO_POINTS: usize = 2;
+/// Warn this long before the descriptor signing key expires
+const EXPIRY_WARNING_HOURS: i64 = 1;
+
+/// State of an onion service's published descriptor, independent of HTTP reachability
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct DescriptorCheck {
+    /// Whether any HSDir returned the descriptor
+    pub found: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision_counter: Option<u64>,
+    /// How long HSDirs keep the descriptor, in minutes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lifetime_minutes: Option<u32>,
+    /// When this revision was first seen. v3 descriptors carry no publication time, so this
+    /// is the closest we get: services publish a new revision at least every couple of hours.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing_key_expires: Option<DateTime<Utc>>,
+    /// Introduction points listed; `None` if unknown (client authorization without a key)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intro_points: Option<usize>,
+    /// Whether the service is likely to become unreachable soon
+    pub at_risk: bool,
+    /// Why the service is at risk
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub risks: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Fewest introduction points a healthy service may list, from `MIN_INTRO_POINTS`
+pub fn min_intro_points_from_env() -> usize {
+    match std::env::var("MIN_INTRO_POINTS") {
+        Ok(value) => value.trim().parse().unwrap_or_else(|_| {
+            println!("⚠ Warning: Invalid MIN_INTRO_POINTS '{}', using {}", value, DEFAULT_MIN_INTRO_POINTS);
+            DEFAULT_MIN_INTRO_POINTS
+        }),
+        Err(_) => DEFAULT_MIN_INTRO_POINTS,
+    }
+}
+
+/// Fetch the descriptor of `onion_url` and assess it against the `previous` check
+pub async fn check_descriptor(
+    config: &ControlConfig,
+    onion_url: &str,
+    client_key: Option<&[u8; 32]>,
+    previous: Option<&DescriptorCheck>,
+    min_intro_points: usize,
+) -> DescriptorCheck {
+    match control::fetch_descriptor(config, onion_url, client_key).await {
+        Ok(descriptor) => assess(&descriptor, previous, min_intro_points, Utc::now()),
+        Err(e) => {
+            let error = format!("{:#}", e);
+            DescriptorCheck {
+                found: false,
+                revision_counter: None,
+                lifetime_minutes: None,
+                published_at: None,
+                signing_key_expires: None,
+                intro_points: None,
+                at_risk: true,
+                risks: vec![error.clone()],
+                error: Some(error),
+                checked_at: Utc::now(),
+            }
+        }
+    }
+}
+
+/// Flag descriptors that are stale or list too few introduction points
+pub fn assess(
+    descriptor: &Descriptor,
+    previous: Option<&DescriptorCheck>,
+    min_intro_points: usize,
+    now: DateTime<Utc>,
+) -> DescriptorCheck {
+    let published_at = previous
+        .filter(|p| p.revision_counter == Some(descriptor.revision_counter))
+        .and_then(|p| p.published_at)
+        .unwrap_or(now);
+
+    let mut risks = Vec::new();
+    let age = now - published_at;
+    if age > Duration::minutes(i64::from(descriptor.lifetime_minutes)) {
+        risks.push(format!(
+            "Descriptor not republished for {}h, longer than its {}min lifetime",
+            age.num_hours(),
+            descriptor.lifetime_minutes
+        ));
+    }
+    if descriptor.signing_key_expires <= now {
+        risks.push(format!(
+            "Descriptor signing key expired at {}",
+            descriptor.signing_key_expires.format("%Y-%m-%d %H:%M UTC")
+        ));
+    } else if descriptor.signing_key_expires - now < Duration::hours(EXPIRY_WARNING_HOURS) {
+        risks.push(format!(
+            "Descriptor signing key expires at {}",
+            descriptor.signing_key_expires.format("%Y-%m-%d %H:%M UTC")
+        ));
+    }
+    match descriptor.intro_points {
+        Some(0) => risks.push("No introduction points listed".to_string()),
+        Some(count) if count < min_intro_points => {
+            risks.push(format!("Only {} introduction point{} listed", count, if count == 1 { "" } else { "s" }));
+        }
+        _ => {}
+    }
+
+    DescriptorCheck {
+        found: true,
+        revision_counter: Some(descriptor.revision_counter),
+        lifetime_minutes: Some(descriptor.lifetime_minutes),
+        published_at: Some(published_at),
+        signing_key_expires: Some(descriptor.signing_key_expires),
+        intro_points: descriptor.intro_points,
+        at_risk: !risks.is_empty(),
+        risks,
+        error: None,
+        checked_at: now,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn descriptor(revision_counter: u64, intro_points: Option<usize>, now: DateTime<Utc>) -> Descriptor {
+        Descriptor {
+            revision_counter,
+            lifetime_minutes: 180,
+            signing_key_expires: now + Duration::hours(30),
+            intro_points,
+        }
+    }
+
+    #[test]
+    fn healthy_descriptors_are_not_at_risk() {
+        let now = Utc::now();
+        let check = assess(&descriptor(5, Some(3), now), None, 2, now);
+        assert!(check.found);
+        assert!(!check.at_risk, "{:?}", check.risks);
+        assert_eq!(check.published_at, Some(now));
+
+        // Client authorization without a key hides the intro points; that alone is no risk
+        assert!(!assess(&descriptor(5, None, now), None, 2, now).at_risk);
+    }
+
+    #[test]
+    fn flags_descriptors_that_are_not_republished() {
+        let first = Utc::now() - Duration::hours(5);
+        let previous = assess(&descriptor(5, Some(3), first), None, 2, first);
+
+        let now = Utc::now();
+        let check = assess(&descriptor(5, Some(3), now), Some(&previous), 2, now);
+        assert_eq!(check.published_at, Some(first));
+        assert!(check.at_risk);
+        assert!(check.risks[0].starts_with("Descriptor not republished for 5h"), "{:?}", check.risks);
+
+        // A new revision resets the publication time
+        let check = assess(&descriptor(6, Some(3), now), Some(&previous), 2, now);
+        assert_eq!(check.published_at, Some(now));
+        assert!(!check.at_risk);
+    }
+
+    #[test]
+    fn flags_few_intro_points_and_expiring_keys() {
+        let now = Utc::now();
+        let check = assess(&descriptor(5, Some(1), now), None, 2, now);
+        assert_eq!(check.risks, ["Only 1 introduction point listed"]);
+
+        let mut expiring = descriptor(5, Some(3), now);
+        expiring.signing_key_expires = now + Duration::minutes(20);
+        let check = assess(&expiring, None, 2, now);
+        assert!(check.at_risk);
+        assert!(check.risks[0].starts_with("Descriptor signing key expires at"));
+    }
+}
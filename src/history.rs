@@ -0,0 +1,268 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::timing::Timings;
+use crate::OnionSite;
+
+/// Version of the `history.json` layout written by this build
+pub const HISTORY_SCHEMA_VERSION: u32 = 1;
+
+/// How long check records are kept, unless overridden by `HISTORY_DAYS`
+const DEFAULT_RETENTION_DAYS: i64 = 90;
+
+/// Per-site check history, keyed by site name
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Default)]
+pub struct HistoryDocument {
+    pub schema_version: u32,
+    pub sites: BTreeMap<String, Vec<CheckRecord>>,
+}
+
+/// Outcome of one check of a site, oldest first within a site's history
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct CheckRecord {
+    pub checked_at: DateTime<Utc>,
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_time_ms: Option<u64>,
+    /// Phases of the fastest response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timings: Option<Timings>,
+    /// Significant change of a landing page since the previous check
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_change: Option<String>,
+}
+
+/// A contiguous period in which a site was not fully online
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct Incident {
+    pub started_at: DateTime<Utc>,
+    /// First check at which the site was back online; `None` while ongoing
+    pub ended_at: Option<DateTime<Utc>>,
+    /// Most severe status seen during the incident
+    pub status: String,
+    pub checks: usize,
+}
+
+impl HistoryDocument {
+    pub fn parse(json: &str) -> Result<Self> {
+        let history: HistoryDocument = serde_json::from_str(json).context("Invalid history document")?;
+        if history.schema_version > HISTORY_SCHEMA_VERSION {
+            bail!(
+                "History has schema_version {}, but this build only understands up to {}",
+                history.schema_version, HISTORY_SCHEMA_VERSION
+            );
+        }
+        Ok(history)
+    }
+
+    /// Append the latest check of every site and drop records past the retention window
+    pub fn record(&mut self, sites: &[OnionSite], now: DateTime<Utc>) {
+        self.schema_version = HISTORY_SCHEMA_VERSION;
+
+        for site in sites {
+            let Some(checked_at) = site.last_checked else {
+                continue;
+            };
+            let records = self.sites.entry(site.name.clone()).or_default();
+            if records.last().map(|r| r.checked_at) == Some(checked_at) {
+                continue;
+            }
+
+            records.push(CheckRecord {
+                checked_at,
+                status: site.status.clone(),
+                response_time_ms: site.fastest_response_ms(),
+                timings: site.fastest_endpoint().and_then(|e| e.timings.clone()),
+                content_change: content_change(site),
+            });
+        }
+
+        let cutoff = now - retention();
+        for records in self.sites.values_mut() {
+            records.retain(|r| r.checked_at >= cutoff);
+        }
+        self.sites.retain(|_, records| !records.is_empty());
+    }
+
+    pub fn for_site(&self, name: &str) -> &[CheckRecord] {
+        self.sites.get(name).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
+
+/// Content changes of a site's endpoints at its latest check
+fn content_change(site: &OnionSite) -> Option<String> {
+    let changes: Vec<String> = site.endpoints.iter()
+        .filter_map(|e| Some((e, e.content.as_ref()?.change.as_ref()?)))
+        .map(|(endpoint, change)| match site.endpoints.len() {
+            1 => change.clone(),
+            _ => format!("{}: {}", endpoint.url, change),
+        })
+        .collect();
+    (!changes.is_empty()).then(|| changes.join("; "))
+}
+
+fn retention() -> Duration {
+    let days = std::env::var("HISTORY_DAYS")
+        .ok()
+        .and_then(|d| d.parse().ok())
+        .filter(|d| *d > 0)
+        .unwrap_or(DEFAULT_RETENTION_DAYS);
+    Duration::days(days)
+}
+
+/// How much a status counts towards availability
+pub fn availability(status: &str) -> f64 {
+    match status {
+        "online" => 1.0,
+        "partial" => 0.5,
+        _ => 0.0,
+    }
+}
+
+/// Percentage availability over the records checked since `since`
+pub fn uptime(records: &[CheckRecord], since: DateTime<Utc>) -> Option<f64> {
+    let window: Vec<&CheckRecord> = records.iter()
+        .filter(|r| r.checked_at >= since && r.status != "unknown")
+        .collect();
+    if window.is_empty() {
+        return None;
+    }
+
+    let total: f64 = window.iter().map(|r| availability(&r.status)).sum();
+    Some(total / window.len() as f64 * 100.0)
+}
+
+/// Time of the most recent status change
+pub fn last_change(records: &[CheckRecord]) -> Option<DateTime<Utc>> {
+    records.windows(2)
+        .rev()
+        .find(|pair| pair[0].status != pair[1].status)
+        .map(|pair| pair[1].checked_at)
+}
+
+/// Checks at which a landing page changed significantly, oldest first
+pub fn content_changes(records: &[CheckRecord]) -> Vec<(DateTime<Utc>, &str)> {
+    records.iter()
+        .filter_map(|r| Some((r.checked_at, r.content_change.as_deref()?)))
+        .collect()
+}
+
+/// A change of status between two consecutive checks
+#[derive(Debug, Clone)]
+pub struct Transition {
+    pub at: DateTime<Utc>,
+    pub from: String,
+    pub to: String,
+}
+
+/// Status changes between known statuses, oldest first
+pub fn transitions(records: &[CheckRecord]) -> Vec<Transition> {
+    records.windows(2)
+        .filter(|pair| pair[0].status != pair[1].status)
+        .filter(|pair| pair[0].status != "unknown" && pair[1].status != "unknown")
+        .map(|pair| Transition {
+            at: pair[1].checked_at,
+            from: pair[0].status.clone(),
+            to: pair[1].status.clone(),
+        })
+        .collect()
+}
+
+/// Periods in which the site was not fully online, oldest first
+pub fn incidents(records: &[CheckRecord]) -> Vec<Incident> {
+    let mut incidents: Vec<Incident> = Vec::new();
+    let mut current: Option<Incident> = None;
+
+    for record in records {
+        if record.status == "online" || record.status == "unknown" {
+            if let Some(mut incident) = current.take() {
+                incident.ended_at = Some(record.checked_at);
+                incidents.push(incident);
+            }
+            continue;
+        }
+
+        let incident = current.get_or_insert_with(|| Incident {
+            started_at: record.checked_at,
+            ended_at: None,
+            status: record.status.clone(),
+            checks: 0,
+        });
+        incident.checks += 1;
+        if availability(&record.status) < availability(&incident.status) {
+            incident.status = record.status.clone();
+        }
+    }
+
+    incidents.extend(current);
+    incidents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap()
+    }
+
+    /// Records `(hours before now, status)`, oldest first
+    fn records(checks: &[(i64, &str)]) -> Vec<CheckRecord> {
+        checks.iter().map(|(hours, status)| CheckRecord {
+            checked_at: now() - Duration::hours(*hours),
+            status: status.to_string(),
+            response_time_ms: None,
+            timings: None,
+            content_change: None,
+        }).collect()
+    }
+
+    #[test]
+    fn computes_uptime_within_windows() {
+        let records = records(&[(25, "offline"), (24, "partial"), (12, "unknown"), (6, "online"), (0, "online")]);
+        let day = now() - Duration::days(1);
+
+        // The check exactly at the start of the window counts, unknown ones do not
+        assert_eq!(uptime(&records, day), Some((0.5 + 1.0 + 1.0) / 3.0 * 100.0));
+        assert_eq!(uptime(&records, day + Duration::seconds(1)), Some(100.0));
+        assert_eq!(uptime(&records, now() - Duration::days(7)), Some(2.5 / 4.0 * 100.0));
+        assert_eq!(uptime(&records, now() + Duration::seconds(1)), None);
+        assert_eq!(uptime(&records[2..3], day), None);
+    }
+
+    #[test]
+    fn summarises_incidents_and_changes() {
+        let records = records(&[
+            (10, "online"),
+            (9, "partial"),
+            (8, "offline"),
+            (7, "online"),
+            (6, "unknown"),
+            (5, "online"),
+            (4, "error-502"),
+            (3, "offline"),
+        ]);
+
+        let incidents = incidents(&records);
+        assert_eq!(incidents.len(), 2);
+        assert_eq!(incidents[0].started_at, now() - Duration::hours(9));
+        assert_eq!(incidents[0].ended_at, Some(now() - Duration::hours(7)));
+        assert_eq!(incidents[0].status, "offline");
+        assert_eq!(incidents[0].checks, 2);
+        // Still open at the end of the history
+        assert_eq!(incidents[1].started_at, now() - Duration::hours(4));
+        assert_eq!(incidents[1].ended_at, None);
+        assert_eq!(incidents[1].status, "error-502");
+        assert_eq!(incidents[1].checks, 2);
+
+        assert_eq!(last_change(&records), Some(now() - Duration::hours(3)));
+        assert_eq!(last_change(&records[..1]), None);
+        let transitions = transitions(&records);
+        assert_eq!(transitions.len(), 5);
+        assert!(transitions.iter().all(|t| t.from != "unknown" && t.to != "unknown"));
+    }
+}
@@ -1,29 +1,282 @@
-use anyhow::{Context, Result};
+#[cfg(feature = "arti")]
+mod arti;
+mod clientauth;
+mod control;
+mod descriptor;
+mod document;
+mod fingerprint;
+mod freshness;
+mod history;
+mod output;
+mod proxy;
+mod redirect;
+mod render;
+mod schema;
+mod slug;
+mod store;
+mod svg;
+mod timing;
+mod tls;
+mod vantage;
+mod verify;
+
+use anyhow::{bail, Context, Result};
 use chrono::{DateTime, Utc};
 use reqwest::Client;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
 use std::fs;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 struct OnionSite {
     title: String,
     name: String,
+    /// Primary address (the first endpoint), kept for consumers that only show one
     onion_address: String,
+    /// Aggregate status across all endpoints: `online`, `partial`, `offline`, ...
     status: String,
     prev_status: String,
     last_checked: Option<DateTime<Utc>>,
+    #[serde(default)]
+    endpoints: Vec<Endpoint>,
+    #[serde(flatten)]
+    metadata: ProjectMetadata,
+    /// Upstream fields this tool does not know about, preserved verbatim
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    extra: BTreeMap<String, serde_json::Value>,
+    /// Result of comparing the clearnet site's Onion-Location with our endpoints
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    onion_location: Option<verify::OnionLocationCheck>,
+    /// Result of checking published ownership proofs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ownership: Option<verify::OwnershipCheck>,
+}
+
+/// Descriptive project fields carried through from the upstream ecosystem list
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Default)]
+struct ProjectMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    category: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    official_website: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    github: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+}
+
+/// Remove the fields that `Probe` cannot deserialize on their own (a string where a list is
+/// expected, ...), so one mistyped upstream field does not lose the whole entry. `Probe` must
+/// accept an object holding any single field it knows.
+fn take_mistyped<Probe: DeserializeOwned>(fields: &mut serde_json::Map<String, serde_json::Value>) -> BTreeMap<String, serde_json::Value> {
+    let mistyped: Vec<String> = fields.iter()
+        .filter(|(key, value)| {
+            let single = serde_json::Map::from_iter([((*key).clone(), (*value).clone())]);
+            serde_json::from_value::<Probe>(serde_json::Value::Object(single)).is_err()
+        })
+        .map(|(key, _)| key.clone())
+        .collect();
+    mistyped.into_iter()
+        .filter_map(|key| fields.remove_entry(&key))
+        .collect()
+}
+
+/// A single onion address of a service (mirror, HTTPS variant, API onion, ...)
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+struct Endpoint {
+    url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    status: String,
+    prev_status: String,
+    last_checked: Option<DateTime<Utc>>,
+    #[serde(default)]
+    response_time_ms: Option<u64>,
+    /// Where the time of the last successful check went (fastest vantage point)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timings: Option<timing::Timings>,
+    /// Certificate presented by an HTTPS endpoint at the last check
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    certificate: Option<tls::CertificateCheck>,
+    /// Redirects the endpoint answered with at the last check
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    redirects: Option<redirect::RedirectCheck>,
+    /// Fingerprint of the landing page at the last response, to notice when it is replaced
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content: Option<fingerprint::ContentFingerprint>,
+    /// Why the last check failed, from the Tor control port or the embedded Arti client
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    diagnosis: Option<control::Diagnosis>,
+    /// Freshness of the onion service descriptor, when a Tor control port is configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    descriptor: Option<freshness::DescriptorCheck>,
+    /// Result through each SOCKS proxy when several are configured; `status` is their consensus
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    vantages: Vec<vantage::VantageResult>,
+}
+
+impl Endpoint {
+    fn new(url: String, label: Option<String>) -> Self {
+        Endpoint {
+            url,
+            label,
+            status: "unknown".to_string(),
+            prev_status: "unknown".to_string(),
+            last_checked: None,
+            response_time_ms: None,
+            timings: None,
+            certificate: None,
+            redirects: None,
+            content: None,
+            diagnosis: None,
+            descriptor: None,
+            vantages: Vec::new(),
+        }
+    }
+}
+
+impl OnionSite {
+    /// Parse a site from `onions.json`. Metadata fields of an unexpected type were carried
+    /// over from upstream into `extra` and are put back there.
+    fn from_value(value: serde_json::Value) -> serde_json::Result<Self> {
+        let serde_json::Value::Object(mut fields) = value else {
+            return serde_json::from_value(value);
+        };
+        let mistyped = take_mistyped::<ProjectMetadata>(&mut fields);
+        let mut site: OnionSite = serde_json::from_value(serde_json::Value::Object(fields))?;
+        site.extra.extend(mistyped);
+        Ok(site)
+    }
+
+    /// Fill in `endpoints` for sites written before multi-endpoint support
+    fn ensure_endpoints(&mut self) {
+        if self.endpoints.is_empty() {
+            let mut endpoint = Endpoint::new(self.onion_address.clone(), None);
+            endpoint.status = self.status.clone();
+            endpoint.prev_status = self.prev_status.clone();
+            endpoint.last_checked = self.last_checked;
+            self.endpoints.push(endpoint);
+        }
+    }
+
+    fn has_address(&self, address: &str) -> bool {
+        self.endpoints.iter().any(|e| same_address(&e.url, address))
+    }
+
+    /// Endpoint that answered fastest
+    fn fastest_endpoint(&self) -> Option<&Endpoint> {
+        self.endpoints.iter()
+            .filter(|e| e.status == "online" && e.response_time_ms.is_some())
+            .min_by_key(|e| e.response_time_ms)
+    }
+
+    /// Fastest response among the endpoints that answered
+    fn fastest_response_ms(&self) -> Option<u64> {
+        self.fastest_endpoint().and_then(|e| e.response_time_ms)
+    }
+}
+
+/// Combine per-endpoint results into one service status
+fn aggregate_status(endpoints: &[Endpoint]) -> String {
+    vantage::consensus(endpoints.iter().map(|e| e.status.as_str()))
 }
 
 // Structures for parsing GitHub repository data
 #[derive(Debug, Deserialize)]
 struct GitHubProject {
+    /// Optional stable identifier; takes precedence over `name` when deriving the slug
+    #[serde(default)]
+    id: Option<String>,
     #[serde(default)]
     name: String,
     #[serde(default)]
-    onion: Option<String>,
+    onion: Option<OnionField>,
+    /// Additional onion mirrors of the same service
+    #[serde(default)]
+    mirrors: Vec<OnionEntry>,
+    #[serde(flatten)]
+    metadata: ProjectMetadata,
+    /// Everything else in the upstream entry
+    #[serde(flatten)]
+    extra: BTreeMap<String, serde_json::Value>,
+}
+
+/// Upstream `onion` is either a single address or a list of addresses
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum OnionField {
+    One(OnionEntry),
+    Many(Vec<OnionEntry>),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum OnionEntry {
+    Url(String),
+    Labeled {
+        url: String,
+        #[serde(default)]
+        label: Option<String>,
+    },
+}
+
+impl GitHubProject {
+    /// Parse one upstream entry, keeping fields of an unexpected type in `extra`.
+    /// Returns the names of those fields.
+    fn from_value(value: serde_json::Value) -> serde_json::Result<(Self, Vec<String>)> {
+        let serde_json::Value::Object(mut fields) = value else {
+            return serde_json::from_value(value).map(|project| (project, Vec::new()));
+        };
+        let mistyped = take_mistyped::<GitHubProject>(&mut fields);
+        let mut project: GitHubProject = serde_json::from_value(serde_json::Value::Object(fields))?;
+        let names = mistyped.keys().cloned().collect();
+        project.extra.extend(mistyped);
+        Ok((project, names))
+    }
+
+    /// All onion endpoints of the project, normalized, in upstream order
+    fn endpoints(&self) -> Vec<Endpoint> {
+        let entries: Vec<&OnionEntry> = match &self.onion {
+            Some(OnionField::One(entry)) => vec![entry],
+            Some(OnionField::Many(entries)) => entries.iter().collect(),
+            None => Vec::new(),
+        };
+
+        let mut endpoints: Vec<Endpoint> = Vec::new();
+        for entry in entries.into_iter().chain(&self.mirrors) {
+            let (raw, label) = match entry {
+                OnionEntry::Url(url) => (url, None),
+                OnionEntry::Labeled { url, label } => (url, label.clone()),
+            };
+            if let Some(url) = normalize_onion_address(raw) {
+                if !endpoints.iter().any(|e| same_address(&e.url, &url)) {
+                    endpoints.push(Endpoint::new(url, label));
+                }
+            }
+        }
+        endpoints
+    }
+}
+
+/// Skip WIP/placeholder entries and default to HTTP, as many .onion sites don't support HTTPS
+fn normalize_onion_address(raw: &str) -> Option<String> {
+    let onion = raw.trim();
+    if onion == ".onion" || onion.is_empty() {
+        return None;
+    }
+
+    if onion.starts_with("http://") || onion.starts_with("https://") {
+        Some(onion.to_string())
+    } else {
+        Some(format!("http://{}", onion))
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -37,75 +290,328 @@ struct GitHubFile {
 #[derive(Debug, Serialize, Deserialize)]
 struct CheckResult {
     site: OnionSite,
+    /// Fastest response among the endpoints that answered
     response_time_ms: Option<u64>,
 }
 
-async fn check_onion_site_curl(site: &OnionSite, proxy_host: &str, proxy_port: u16) -> CheckResult {
-    println!("Checking: {} ({})", site.title, site.onion_address);
+impl From<OnionSite> for CheckResult {
+    /// Rebuild a result from a site as saved in `onions.json`
+    fn from(site: OnionSite) -> Self {
+        let response_time_ms = site.fastest_response_ms();
+        CheckResult { site, response_time_ms }
+    }
+}
+
+/// Settings for the HTTP checks
+struct CheckOptions {
+    cert_expiry_warning_days: i64,
+    max_redirects: usize,
+    content_change_threshold: u32,
+    content_snapshots: bool,
+}
+
+impl CheckOptions {
+    fn from_env() -> Self {
+        CheckOptions {
+            cert_expiry_warning_days: tls::expiry_warning_days_from_env(),
+            max_redirects: redirect::max_redirects_from_env(),
+            content_change_threshold: fingerprint::change_threshold_from_env(),
+            content_snapshots: fingerprint::snapshots_from_env(),
+        }
+    }
+}
+
+/// What a check saw besides reachability, which is the same from every vantage point
+#[derive(Default)]
+struct ResponseDetails {
+    certificate: Option<tls::CertificateCheck>,
+    redirects: Option<redirect::RedirectCheck>,
+    /// Body of the final response, when it was the landing page
+    body: Option<Vec<u8>>,
+    /// Where the embedded Arti client failed to connect
+    diagnosis: Option<control::Diagnosis>,
+}
+
+/// Check an endpoint through every vantage point and combine the results
+async fn check_endpoint(endpoint: &Endpoint, title: &str, vantages: &[vantage::Vantage], options: &CheckOptions) -> Endpoint {
+    let mut results = Vec::new();
+    let mut details = ResponseDetails::default();
+    for vantage in vantages {
+        if vantages.len() > 1 {
+            println!("Checking: {} ({}) via {}", title, endpoint.url, vantage.name);
+        } else {
+            println!("Checking: {} ({})", title, endpoint.url);
+        }
+        let (result, seen) = check_onion_site(&endpoint.url, title, vantage, options).await;
+        results.push(result);
+        details.certificate = details.certificate.or(seen.certificate);
+        details.redirects = details.redirects.or(seen.redirects);
+        details.body = details.body.or(seen.body);
+        details.diagnosis = details.diagnosis.or(seen.diagnosis);
+    }
+
+    let mut updated = endpoint.clone();
+    updated.prev_status = endpoint.status.clone();
+    updated.last_checked = Some(Utc::now());
+    updated.diagnosis = details.diagnosis;
+    updated.status = vantage::vantage_consensus(results.iter().map(|r| r.status.as_str()));
+    let fastest = results.iter().filter(|r| r.response_time_ms.is_some()).min_by_key(|r| r.response_time_ms);
+    updated.response_time_ms = fastest.and_then(|r| r.response_time_ms);
+    updated.timings = fastest.and_then(|r| r.timings.clone());
+    updated.certificate = details.certificate;
+    updated.redirects = details.redirects;
+    updated.content = fingerprint::update(
+        endpoint.content.as_ref(),
+        details.body.as_deref(),
+        options.content_change_threshold,
+        options.content_snapshots,
+        Utc::now(),
+    );
+    if let Some(change) = updated.content.as_ref().and_then(|content| content.change.as_ref()) {
+        println!("  ⚠ {} - {}", endpoint.url, change);
+    }
 
-    let start = std::time::Instant::now();
+    if results.len() > 1 {
+        let online = results.iter().filter(|r| r.status == "online").count();
+        println!("  → {} - {} ({}/{} vantage points online)", endpoint.url, updated.status, online, results.len());
+        updated.vantages = results;
+    } else {
+        updated.vantages.clear();
+    }
+    updated
+}
 
-    // Use curl with SOCKS5h to properly resolve .onion addresses through the proxy
+/// Check `url` through `vantage`, following redirects between onion services. Status comes
+/// from the last response; response time and timings from the first, which sets up the circuit.
+async fn check_onion_site(
+    url: &str,
+    title: &str,
+    vantage: &vantage::Vantage,
+    options: &CheckOptions,
+) -> (vantage::VantageResult, ResponseDetails) {
+    let mut result = vantage::VantageResult {
+        vantage: vantage.name.clone(),
+        status: "offline".to_string(),
+        response_time_ms: None,
+        timings: None,
+        checked_at: Utc::now(),
+    };
+    let mut details = ResponseDetails::default();
+    let mut redirects = redirect::RedirectCheck::new(url);
+    let host = url_host(url);
+
+    let mut next = Some(url.to_string());
+    while let Some(request_url) = next.take() {
+        let first = redirects.chain.is_empty();
+        let response = match &vantage.transport {
+            vantage::Transport::Socks(proxy) => curl_request(&request_url, proxy).await,
+            #[cfg(feature = "arti")]
+            vantage::Transport::Arti(client) => match client.request(&request_url).await {
+                Ok(response) => Ok(Some(response)),
+                Err(diagnosis) => {
+                    if first {
+                        details.diagnosis = Some(diagnosis);
+                    }
+                    Ok(None)
+                }
+            },
+        };
+        let response = match response {
+            Ok(Some(response)) => response,
+            Ok(None) if first => {
+                println!("  ✗ {} - Offline (request failed)", title);
+                break;
+            }
+            Err(e) if first => {
+                println!("  ✗ {} - Failed to execute curl: {}", title, e);
+                break;
+            }
+            // The endpoint itself answered; its status stays that of the redirect
+            Ok(None) | Err(_) => {
+                println!("    ⚠ Redirect target {} did not respond", request_url);
+                redirects.warnings.push(format!("Redirect target {} did not respond", request_url));
+                break;
+            }
+        };
+        let code = response.code;
+        let response_time = response.timings.total_ms;
+        details.body = fingerprint::is_landing_page(code).then_some(response.body);
+
+        // Treat 2xx, 3xx, and 4xx (client errors like 405) as "online" - service responded
+        if (200..500).contains(&code) {
+            result.status = "online".to_string();
+        } else if code >= 500 {
+            // 5xx server errors
+            result.status = format!("error-{}", code);
+        } else {
+            result.status = "offline".to_string();
+        }
+
+        if first {
+            match code {
+                200..=499 => println!("  ✓ {} - Online HTTP {} ({}ms)", title, code, response_time),
+                500.. => println!("  ⚠ {} - Server Error HTTP {} ({}ms)", title, code, response_time),
+                _ => println!("  ✗ {} - Connection failed ({}ms)", title, response_time),
+            }
+            if let Some(circuit) = response.timings.circuit_ms {
+                println!("    circuit {}ms, first byte after {}ms", circuit, response.timings.ttfb_ms.unwrap_or_default());
+            }
+            result.response_time_ms = Some(response_time);
+            result.timings = Some(response.timings);
+        } else {
+            println!("    ↪ HTTP {} from {} ({}ms)", code, request_url, response_time);
+        }
+
+        // The endpoint's own certificate, possibly only served after a redirect to HTTPS
+        if details.certificate.is_none() && url_host(&request_url) == host {
+            details.certificate = tls::parse_curl(&response.certs, &host, options.cert_expiry_warning_days, Utc::now());
+            for warning in details.certificate.iter().flat_map(|c| &c.warnings) {
+                println!("    ⚠ {}", warning);
+            }
+        }
+
+        if let (300..=399, Some(location)) = (code, &response.redirect_url) {
+            next = redirects.record(&request_url, code, location, options.max_redirects);
+            if next.is_none() {
+                println!("    ↪ HTTP {} to {} (not followed)", code, location);
+            }
+        }
+    }
+
+    for warning in &redirects.warnings {
+        println!("    ⚠ {}", warning);
+    }
+    if !redirects.chain.is_empty() {
+        details.redirects = Some(redirects);
+    }
+    (result, details)
+}
+
+/// What one request saw
+struct HttpResponse {
+    code: u16,
+    timings: timing::Timings,
+    /// Absolute target of the `Location` header
+    redirect_url: Option<String>,
+    /// Certificates in `tls::CURL_WRITE_OUT` format; empty without curl
+    certs: String,
+    /// Up to `MAX_BODY_BYTES` of the body
+    body: Vec<u8>,
+}
+
+/// Bytes of each response body kept for fingerprinting
+const MAX_BODY_BYTES: u64 = 1024 * 1024;
+
+/// Request `url` once, without following redirects. `None` if curl failed.
+async fn curl_request(url: &str, proxy: &proxy::SocksProxy) -> std::io::Result<Option<HttpResponse>> {
+    static REQUESTS: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+    let body_path = std::env::temp_dir().join(format!(
+        "onion-monitor-{}-{}.body",
+        std::process::id(),
+        REQUESTS.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+    ));
+
+    // The proxy URL always makes the proxy resolve .onion addresses (socks5h/socks4a).
+    // The verbose log is only read for the timestamps of the SOCKS exchange.
+    // `--insecure` keeps certificate problems from failing the check; they are reported separately.
     let output = tokio::process::Command::new("curl")
-        .arg("--socks5-hostname")
-        .arg(format!("{}:{}", proxy_host, proxy_port))
-        .arg(&site.onion_address)
+        .arg("--proxy")
+        .arg(proxy.curl_url())
+        .arg(url)
         .arg("--max-time")
         .arg("60")
         .arg("--write-out")
-        .arg("%{http_code}")
+        .arg(format!("{}\n%{{redirect_url}}\n{}", timing::CURL_WRITE_OUT, tls::CURL_WRITE_OUT))
         .arg("--silent")
+        .arg("--verbose")
+        .arg("--trace-time")
         .arg("--output")
-        .arg("/dev/null")
-        .arg("--insecure") // Accept self-signed certs for .onion sites
+        .arg(&body_path)
+        .arg("--insecure")
         .output()
         .await;
+    let body = read_body(&body_path).await;
+    let _ = tokio::fs::remove_file(&body_path).await;
+
+    let output = output?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.splitn(3, '\n');
+    let (timing_line, redirect_url, certs) = (lines.next(), lines.next(), lines.next());
+    let Some((code, timings)) = timing::parse_curl(timing_line.unwrap_or_default(), &String::from_utf8_lossy(&output.stderr)) else {
+        return Ok(None);
+    };
+    Ok(Some(HttpResponse {
+        code,
+        timings,
+        redirect_url: redirect_url.map(str::trim).filter(|u| !u.is_empty()).map(str::to_string),
+        certs: certs.unwrap_or_default().to_string(),
+        body,
+    }))
+}
+
+/// The start of a response body curl wrote to `path`; empty if there was none
+async fn read_body(path: &std::path::Path) -> Vec<u8> {
+    use tokio::io::AsyncReadExt;
+
+    let mut body = Vec::new();
+    if let Ok(file) = tokio::fs::File::open(path).await {
+        let _ = file.take(MAX_BODY_BYTES).read_to_end(&mut body).await;
+    }
+    body
+}
 
-    let response_time = start.elapsed().as_millis() as u64;
+fn url_host(url: &str) -> String {
+    reqwest::Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_ascii_lowercase)).unwrap_or_default()
+}
 
+/// Check every endpoint of a site independently and aggregate the results
+async fn check_site(
+    site: &OnionSite,
+    vantages: &[vantage::Vantage],
+    options: &CheckOptions,
+    control: Option<&control::ControlConfig>,
+    client_auth: &clientauth::ClientAuthKeys,
+) -> CheckResult {
     let mut updated_site = site.clone();
     updated_site.prev_status = site.status.clone();
-    updated_site.last_checked = Some(Utc::now());
+    updated_site.endpoints.clear();
 
-    match output {
-        Ok(result) => {
-            if result.status.success() {
-                let status_code = String::from_utf8_lossy(&result.stdout);
-                let code: u16 = status_code.trim().parse().unwrap_or(0);
-
-                // Treat 2xx, 3xx, and 4xx (client errors like 405) as "online" - service responded
-                if code >= 200 && code < 500 {
-                    updated_site.status = "online".to_string();
-                    println!("  ✓ {} - Online HTTP {} ({}ms)", site.title, code, response_time);
-                } else if code >= 500 {
-                    // 5xx server errors
-                    updated_site.status = format!("error-{}", code);
-                    println!("  ⚠ {} - Server Error HTTP {} ({}ms)", site.title, code, response_time);
-                } else {
-                    updated_site.status = "offline".to_string();
-                    println!("  ✗ {} - Connection failed ({}ms)", site.title, response_time);
-                }
-                CheckResult {
-                    site: updated_site,
-                    response_time_ms: Some(response_time),
-                }
-            } else {
-                updated_site.status = "offline".to_string();
-                println!("  ✗ {} - Offline (curl error)", site.title);
-                CheckResult {
-                    site: updated_site,
-                    response_time_ms: None,
-                }
-            }
+    for (i, endpoint) in site.endpoints.iter().enumerate() {
+        if i > 0 {
+            // Small delay between checks to be nice
+            tokio::time::sleep(Duration::from_secs(2)).await;
         }
-        Err(e) => {
-            updated_site.status = "offline".to_string();
-            println!("  ✗ {} - Failed to execute curl: {}", site.title, e);
-            CheckResult {
-                site: updated_site,
-                response_time_ms: None,
-            }
+        let mut checked = check_endpoint(endpoint, &site.title, vantages, options).await;
+        if let (Some(control), "offline", Some(proxy)) = (control, checked.status.as_str(), vantages[0].transport.socks()) {
+            let client_key = client_auth.for_url(&checked.url).map(|k| k.secret());
+            // The control port belongs to the Tor instance behind the primary proxy
+            checked.diagnosis = Some(control::diagnose(control, &checked.url, client_key, proxy).await);
+        }
+        if let Some(diagnosis) = &checked.diagnosis {
+            println!(
+                "  🔍 Failed at {}{}",
+                diagnosis.failed_at.label(),
+                diagnosis.details.as_deref().map(|d| format!(": {}", d)).unwrap_or_default()
+            );
         }
+        updated_site.endpoints.push(checked);
+    }
+
+    updated_site.status = aggregate_status(&updated_site.endpoints);
+    updated_site.last_checked = Some(Utc::now());
+
+    let response_time_ms = updated_site.fastest_response_ms();
+
+    if updated_site.endpoints.len() > 1 {
+        println!("  → {} - {}", site.title, updated_site.status);
+    }
+
+    CheckResult {
+        site: updated_site,
+        response_time_ms,
     }
 }
 
@@ -141,7 +647,9 @@ async fn fetch_onion_addresses_from_github() -> Result<Vec<OnionSite>> {
 
     println!("  Found {} JSON files in directory", json_files.len());
 
-    let mut sites = Vec::new();
+    let mut sites: Vec<OnionSite> = Vec::new();
+    // Slug -> onion address, used to detect duplicate entries and name collisions
+    let mut seen_names: HashMap<String, String> = HashMap::new();
 
     // Fetch each JSON file
     for file in json_files {
@@ -157,37 +665,45 @@ async fn fetch_onion_addresses_from_github() -> Result<Vec<OnionSite>> {
             match file_request.send().await {
                 Ok(response) => {
                     if let Ok(text) = response.text().await {
-                        if let Ok(projects) = serde_json::from_str::<Vec<GitHubProject>>(&text) {
-                            for project in projects {
-                                if let Some(onion) = project.onion {
-                                    // Skip WIP/placeholder entries
-                                    if onion == ".onion" || onion.is_empty() {
-                                        continue;
-                                    }
-
-                                    // Normalize onion address - use HTTP by default as many .onion sites don't support HTTPS
-                                    let onion_address = if onion.starts_with("http://") || onion.starts_with("https://") {
-                                        onion
-                                    } else {
-                                        format!("http://{}", onion)
-                                    };
-
-                                    // Create kebab-case name from title
-                                    let name = project.name
-                                        .to_lowercase()
-                                        .replace(' ', "-")
-                                        .replace('_', "-");
-
-                                    sites.push(OnionSite {
-                                        title: project.name.clone(),
-                                        name,
-                                        onion_address,
-                                        status: "unknown".to_string(),
-                                        prev_status: "unknown".to_string(),
-                                        last_checked: None,
-                                    });
-                                }
+                        for project in parse_projects(&text, &file.name) {
+                            let endpoints = project.endpoints();
+                            let Some(primary) = endpoints.first().map(|e| e.url.clone()) else {
+                                continue;
+                            };
+
+                            // Prefer the explicit id, fall back to a slug of the title
+                            let base_name = slug::slugify(
+                                project.id.as_deref().unwrap_or(&project.name)
+                            );
+
+                            if seen_names.get(&base_name) == Some(&primary) {
+                                println!("  ⚠ Warning: Skipping duplicate entry '{}' in {}", project.name, file.name);
+                                continue;
+                            }
+
+                            let taken: HashSet<String> = seen_names.keys().cloned().collect();
+                            let name = slug::disambiguate(&base_name, &taken);
+                            if name != base_name {
+                                println!(
+                                    "  ⚠ Warning: Name '{}' from '{}' collides with another project, using '{}'",
+                                    base_name, project.name, name
+                                );
                             }
+                            seen_names.insert(name.clone(), primary.clone());
+
+                            sites.push(OnionSite {
+                                title: project.name.clone(),
+                                name,
+                                onion_address: primary,
+                                status: "unknown".to_string(),
+                                prev_status: "unknown".to_string(),
+                                last_checked: None,
+                                endpoints,
+                                metadata: project.metadata,
+                                extra: project.extra,
+                                onion_location: None,
+                                ownership: None,
+                            });
                         }
                     }
                 }
@@ -202,587 +718,418 @@ async fn fetch_onion_addresses_from_github() -> Result<Vec<OnionSite>> {
     Ok(sites)
 }
 
-fn merge_onion_sites(github_sites: Vec<OnionSite>, existing_sites: Vec<OnionSite>) -> Vec<OnionSite> {
-    let mut site_map: HashMap<String, OnionSite> = HashMap::new();
+/// Parse the projects of one upstream file. A bad entry is skipped and mistyped fields are
+/// kept in `extra`, with a warning, instead of losing the whole file.
+fn parse_projects(text: &str, file_name: &str) -> Vec<GitHubProject> {
+    let entries = match serde_json::from_str::<Vec<serde_json::Value>>(text) {
+        Ok(entries) => entries,
+        Err(e) => {
+            println!("  ⚠ Warning: Skipping {}: not a list of projects ({})", file_name, e);
+            return Vec::new();
+        }
+    };
 
-    // First, add all existing sites (preserving their status and history)
-    for site in existing_sites {
-        site_map.insert(site.name.clone(), site);
+    let mut projects = Vec::new();
+    for (i, entry) in entries.into_iter().enumerate() {
+        let entry_name = entry.get("name")
+            .and_then(serde_json::Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("#{}", i + 1));
+        match GitHubProject::from_value(entry) {
+            Ok((project, mistyped)) => {
+                if !mistyped.is_empty() {
+                    println!(
+                        "  ⚠ Warning: Entry '{}' in {} has fields of an unexpected type, kept as is: {}",
+                        entry_name, file_name, mistyped.join(", ")
+                    );
+                }
+                projects.push(project);
+            }
+            Err(e) => println!("  ⚠ Warning: Skipping entry '{}' in {}: {}", entry_name, file_name, e),
+        }
     }
+    projects
+}
+
+fn same_address(a: &str, b: &str) -> bool {
+    a.trim_end_matches('/').eq_ignore_ascii_case(b.trim_end_matches('/'))
+}
 
-    // Then add/update with GitHub sites
-    for github_site in github_sites {
-        site_map.entry(github_site.name.clone())
-            .or_insert(github_site);
+fn merge_onion_sites(github_sites: Vec<OnionSite>, existing_sites: Vec<OnionSite>) -> Vec<OnionSite> {
+    // Start from the existing sites (preserving their status and history)
+    let mut merged = existing_sites;
+    let mut claimed = vec![false; merged.len()];
+
+    for mut github_site in github_sites {
+        // Match on the onion address first so an upstream rename keeps the existing
+        // identity, then on the name so a service that moved address keeps its history
+        let position = merged.iter().enumerate()
+            .position(|(i, s)| !claimed[i] && github_site.endpoints.iter().any(|e| s.has_address(&e.url)))
+            .or_else(|| merged.iter().enumerate()
+                .position(|(i, s)| !claimed[i] && s.name == github_site.name));
+
+        match position {
+            Some(i) => {
+                claimed[i] = true;
+                let site = &mut merged[i];
+                if site.title != github_site.title {
+                    println!("  Renamed: {} -> {} (keeping id '{}')", site.title, github_site.title, site.name);
+                }
+                site.title = github_site.title;
+                site.onion_address = github_site.onion_address;
+                site.metadata = github_site.metadata;
+                site.extra = github_site.extra;
+
+                // Take the upstream endpoint list, carrying over state of known addresses
+                let previous = std::mem::take(&mut site.endpoints);
+                site.endpoints = github_site.endpoints.into_iter()
+                    .map(|endpoint| previous.iter()
+                        .find(|p| same_address(&p.url, &endpoint.url))
+                        .map(|p| Endpoint { label: endpoint.label.clone(), ..p.clone() })
+                        .unwrap_or(endpoint))
+                    .collect();
+            }
+            None => {
+                let taken: HashSet<String> = merged.iter().map(|s| s.name.clone()).collect();
+                let name = slug::disambiguate(&github_site.name, &taken);
+                if name != github_site.name {
+                    println!(
+                        "  ⚠ Warning: Name '{}' is already used by another site, using '{}'",
+                        github_site.name, name
+                    );
+                    github_site.name = name;
+                }
+                merged.push(github_site);
+                claimed.push(true);
+            }
+        }
     }
 
-    // Convert back to Vec and sort by title
-    let mut merged: Vec<OnionSite> = site_map.into_values().collect();
+    // Sort by title
     merged.sort_by(|a, b| a.title.cmp(&b.title));
 
     merged
 }
 
-fn generate_html(results: &[CheckResult]) -> String {
-    let mut cards = String::new();
+/// Fetch the descriptor behind every endpoint and flag services at risk of going offline
+async fn check_descriptors(
+    results: &mut [CheckResult],
+    config: &control::ControlConfig,
+    client_auth: &clientauth::ClientAuthKeys,
+) {
+    println!("\n🧭 Checking onion service descriptors...");
+    let min_intro_points = freshness::min_intro_points_from_env();
+    // Endpoints on the same onion service share a descriptor
+    let mut checked: HashMap<[u8; 32], freshness::DescriptorCheck> = HashMap::new();
+
+    for result in results.iter_mut() {
+        let site = &mut result.site;
+        for endpoint in site.endpoints.iter_mut() {
+            let Ok(key) = descriptor::identity_key(&endpoint.url) else {
+                endpoint.descriptor = None;
+                continue;
+            };
+            if let Some(check) = checked.get(&key) {
+                endpoint.descriptor = Some(check.clone());
+                continue;
+            }
 
-    for result in results {
-        let site = &result.site;
-        let status_class = match site.status.as_str() {
-            "online" => "status-online",
-            "offline" => "status-offline",
-            "unknown" => "status-unknown",
-            _ => "status-error",
-        };
+            let check = freshness::check_descriptor(
+                config,
+                &endpoint.url,
+                client_auth.for_url(&endpoint.url).map(|k| k.secret()),
+                endpoint.descriptor.as_ref(),
+                min_intro_points,
+            ).await;
+            if check.at_risk {
+                println!("  ⚠ {} - At risk: {}", site.title, check.risks.join("; "));
+            } else {
+                println!(
+                    "  ✓ {} - Descriptor revision {} with {} introduction points",
+                    site.title,
+                    check.revision_counter.unwrap_or_default(),
+                    check.intro_points.map(|n| n.to_string()).unwrap_or_else(|| "hidden".to_string())
+                );
+            }
+            checked.insert(key, check.clone());
+            endpoint.descriptor = Some(check);
+        }
+    }
+}
+
+/// Check Onion-Location and, when enabled, published ownership proofs of every site
+async fn verify_sites(results: &mut [CheckResult], proxy: Option<&proxy::SocksProxy>) -> Result<()> {
+    let clearnet_mode = verify::ClearnetMode::from_env();
+    let mut proof_methods = verify::ProofMethod::from_env();
+    if clearnet_mode == verify::ClearnetMode::Off {
+        // An Onion-Location result from an earlier run would otherwise keep showing as current
+        for result in results.iter_mut() {
+            result.site.onion_location = None;
+        }
+        // Only the onion-side proof works without clearnet access, and it can at most be claimed
+        proof_methods.retain(|m| *m == verify::ProofMethod::OnionBacklink);
+    }
 
-        let status_text = match site.status.as_str() {
-            "online" => "Online",
-            "offline" => "Offline",
-            "unknown" => "Unknown",
-            _ => "Error",
+    if clearnet_mode == verify::ClearnetMode::Off && proof_methods.is_empty() {
+        return Ok(());
+    }
+
+    println!("\n🔎 Verifying onion addresses against clearnet websites...");
+    let proxy_url = match proxy.map(proxy::SocksProxy::reqwest_url) {
+        Some(Ok(url)) => url,
+        Some(Err(e)) => {
+            println!("  ⚠ Skipping verification: {}", e);
+            return Ok(());
+        }
+        None => {
+            println!("  ⚠ Skipping verification: it needs a SOCKS proxy, not the embedded Arti client");
+            return Ok(());
+        }
+    };
+    let clearnet = verify::clearnet_client(clearnet_mode, &proxy_url)
+        .context("Failed to build clearnet HTTP client")?;
+    let onion = verify::onion_client(&proxy_url)
+        .context("Failed to build onion HTTP client")?;
+
+    for result in results.iter_mut() {
+        let site = &mut result.site;
+        let Some(website) = site.metadata.official_website.clone() else {
+            site.onion_location = None;
+            site.ownership = None;
+            continue;
         };
+        let onion_urls: Vec<String> = site.endpoints.iter().map(|e| e.url.clone()).collect();
+
+        if clearnet_mode != verify::ClearnetMode::Off {
+            let check = verify::check_onion_location(&clearnet, &website, &onion_urls).await;
+            match check.status {
+                verify::VerificationStatus::Verified => println!("  ✓ {} - Onion-Location verified", site.title),
+                verify::VerificationStatus::Mismatch => println!(
+                    "  ⚠ {} - Onion-Location MISMATCH: {} advertises {}",
+                    site.title, website, check.advertised.as_deref().unwrap_or("?")
+                ),
+                verify::VerificationStatus::Unverified | verify::VerificationStatus::Claimed => {
+                    println!("  - {} - No Onion-Location on {}", site.title, website)
+                }
+                verify::VerificationStatus::Error => println!(
+                    "  ✗ {} - Failed to fetch {}: {}",
+                    site.title, website, check.error.as_deref().unwrap_or("unknown error")
+                ),
+            }
+            site.onion_location = Some(check);
+        }
+
+        if !proof_methods.is_empty() {
+            let check = verify::check_ownership(
+                &clearnet, &onion, &proof_methods, &website, &onion_urls, site.ownership.as_ref()
+            ).await;
+            match check.status {
+                verify::VerificationStatus::Verified => println!("  ✓ {} - Ownership proof verified", site.title),
+                verify::VerificationStatus::Claimed => println!("  - {} - Ownership only claimed by the onion", site.title),
+                verify::VerificationStatus::Mismatch => println!("  ⚠ {} - Ownership proof lists other onions: {}", site.title, check.details.join("; ")),
+                _ => println!("  - {} - No ownership proof ({})", site.title, check.details.join("; ")),
+            }
+            site.ownership = Some(check);
+        }
+    }
 
-        let response_time = result.response_time_ms
-            .map(|ms| format!("{}ms", ms))
-            .unwrap_or_else(|| "N/A".to_string());
-
-        let last_checked = site.last_checked
-            .map(|dt| dt.format("%b %d, %Y %H:%M UTC").to_string())
-            .unwrap_or_else(|| "Never".to_string());
-
-        cards.push_str(&format!(
-            r##"        <div class="card">
-          <div class="card-content">
-            <div class="card-header">
-              <h3 class="card-title">{}</h3>
-              <span class="status-badge {}">{}</span>
-            </div>
-            <div class="onion-url-box">
-              <div class="onion-url-content">
-                <div class="onion-url-left">
-                  <svg class="onion-icon" xmlns="http://www.w3.org/2000/svg" viewBox="0 0 100 100">
-                    <circle cx="50" cy="50" r="45" fill="#7d33b8"/>
-                    <circle cx="50" cy="50" r="35" fill="none" stroke="white" stroke-width="3" opacity="0.8"/>
-                    <circle cx="50" cy="50" r="25" fill="none" stroke="white" stroke-width="3" opacity="0.6"/>
-                    <circle cx="50" cy="50" r="15" fill="none" stroke="white" stroke-width="3" opacity="0.4"/>
-                    <circle cx="50" cy="50" r="6" fill="white"/>
-                  </svg>
-                  <span class="onion-label">.onion</span>
-                </div>
-              </div>
-              <div class="onion-url">{}</div>
-            </div>
-            <div class="card-meta">
-              <div class="meta-item">
-                <span class="meta-label">Response:</span>
-                <span class="meta-value">{}</span>
-              </div>
-              <div class="meta-item">
-                <span class="meta-label">Checked:</span>
-                <span class="meta-value">{}</span>
-              </div>
-            </div>
-          </div>
-        </div>
-"##,
-            site.title,
-            status_class,
-            status_text,
-            site.onion_address,
-            response_time,
-            last_checked
-        ));
-    }
-
-    let now = Utc::now().format("%b %d, %Y %H:%M UTC");
-
-    format!(
-        r##"<!DOCTYPE html>
-<html lang="en">
-<head>
-  <meta charset="UTF-8">
-  <meta name="viewport" content="width=device-width, initial-scale=1.0">
-  <title>Onion Service Monitor</title>
-  <link rel="icon" type="image/svg+xml" href="data:image/svg+xml,%3Csvg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 100 100'%3E%3Ccircle cx='50' cy='50' r='45' fill='%237d33b8'/%3E%3Ccircle cx='50' cy='50' r='35' fill='none' stroke='white' stroke-width='3' opacity='0.8'/%3E%3Ccircle cx='50' cy='50' r='25' fill='none' stroke='white' stroke-width='3' opacity='0.6'/%3E%3Ccircle cx='50' cy='50' r='15' fill='none' stroke='white' stroke-width='3' opacity='0.4'/%3E%3Ccircle cx='50' cy='50' r='6' fill='white'/%3E%3C/svg%3E">
-  <link rel="preconnect" href="https://fonts.googleapis.com">
-  <link rel="preconnect" href="https://fonts.gstatic.com" crossorigin>
-  <link href="https://fonts.googleapis.com/css2?family=Inter:wght@400;500;600;700&display=swap" rel="stylesheet">
-  <style>
-    * {{
-      margin: 0;
-      padding: 0;
-      box-sizing: border-box;
-    }}
-
-    :root {{
-      /* Tor Purple Palette */
-      --tor-purple-50: #f5e9ff;
-      --tor-purple-100: #ddc2f4;
-      --tor-purple-200: #c59be8;
-      --tor-purple-300: #ad73dd;
-      --tor-purple-400: #964cd1;
-      --tor-purple-500: #7d33b8;
-      --tor-purple-600: #61278f;
-      --tor-purple-700: #461b67;
-      --tor-purple-800: #2b1040;
-      --tor-purple-900: #13051b;
-
-      /* Onion Warm Tones */
-      --onion-500: #e6882d;
-      --onion-600: #b46a22;
-
-      /* Light Mode Colors */
-      --bg-body: #F7FAFC;
-      --bg-card: #FFFFFF;
-      --text-primary: #212335;
-      --text-secondary: #718096;
-      --border-color: #E2E8F0;
-
-      /* Status Colors */
-      --success: #48BB78;
-      --error: #F56565;
-      --warning: #ED8936;
-      --info: #4299E1;
-
-      /* Badge Colors */
-      --badge-wip-bg: rgba(255, 111, 97, 0.15);
-      --badge-wip-color: #D45A4E;
-      --badge-socks5-bg: rgba(15, 76, 129, 0.15);
-      --badge-socks5-color: #0F4C81;
-      --badge-onion-bg: rgba(102, 103, 171, 0.15);
-      --badge-onion-color: #5355A0;
-    }}
-
-    html {{
-      height: 100%;
-    }}
-
-    body {{
-      font-family: 'Roboto', -apple-system, BlinkMacSystemFont, 'Segoe UI', sans-serif;
-      background: var(--bg-body);
-      color: var(--text-primary);
-      line-height: 1.6;
-      min-height: 100vh;
-      margin: 0;
-      padding: 0;
-      display: flex;
-      flex-direction: column;
-    }}
-
-    .page-wrapper {{
-      display: flex;
-      flex-direction: column;
-      min-height: 100vh;
-      width: 100%;
-    }}
-
-    .content-wrapper {{
-      flex: 1;
-    }}
-
-    header {{
-      width: 100%;
-      display: flex;
-      justify-content: space-between;
-      align-items: center;
-      padding: 24px 16px;
-      border-bottom: 1px solid var(--border-color);
-      background: var(--bg-card);
-      box-sizing: border-box;
-    }}
-
-    @media (min-width: 768px) {{
-      header {{
-        padding: 24px 32px;
-      }}
-    }}
-
-    @media (min-width: 1024px) {{
-      header {{
-        padding: 24px 48px;
-      }}
-    }}
-
-    .container {{
-      max-width: 100%;
-      margin: 0 auto;
-      padding: 0 16px 48px 16px;
-    }}
-
-    @media (min-width: 768px) {{
-      .container {{
-        padding: 0 32px 48px 32px;
-      }}
-    }}
-
-    @media (min-width: 1024px) {{
-      .container {{
-        padding: 0 48px 48px 48px;
-      }}
-    }}
-
-    .header-content {{
-      display: flex;
-      align-items: center;
-      gap: 16px;
-    }}
-
-    .tor-logo {{
-      width: 40px;
-      height: 40px;
-      flex-shrink: 0;
-    }}
-
-    .header-text {{
-      display: flex;
-      flex-direction: column;
-      gap: 4px;
-    }}
-
-    h1 {{
-      font-family: 'Inter', sans-serif;
-      color: var(--text-primary);
-      font-size: 1.125rem;
-      font-weight: 600;
-      margin: 0;
-    }}
-
-    .subtitle {{
-      color: var(--text-secondary);
-      font-size: 0.875rem;
-      font-weight: 400;
-    }}
-
-    .section-header {{
-      margin-bottom: 32px;
-      margin-top: 48px;
-    }}
-
-    .section-title {{
-      font-size: 1.25rem;
-      font-weight: 600;
-      color: var(--text-primary);
-      margin: 0;
-    }}
-
-    .cards-grid {{
-      display: grid;
-      grid-template-columns: repeat(auto-fill, minmax(350px, 1fr));
-      gap: 24px;
-      margin-bottom: 48px;
-    }}
-
-    .card {{
-      background: var(--bg-card);
-      border: 1px solid var(--border-color);
-      border-radius: 8px;
-      box-shadow: 0 1px 3px 0 rgba(0, 0, 0, 0.1), 0 1px 2px 0 rgba(0, 0, 0, 0.06);
-      transition: all 0.3s;
-      overflow: hidden;
-    }}
-
-    .card:hover {{
-      transform: translateY(-4px);
-      box-shadow: 0 20px 25px -5px rgba(0, 0, 0, 0.1), 0 10px 10px -5px rgba(0, 0, 0, 0.04);
-    }}
-
-    .card-content {{
-      padding: 16px;
-      display: flex;
-      flex-direction: column;
-      gap: 12px;
-    }}
-
-    .card-header {{
-      display: flex;
-      justify-content: space-between;
-      align-items: flex-start;
-      gap: 8px;
-    }}
-
-    .card-title {{
-      font-size: 1rem;
-      font-weight: 600;
-      color: var(--text-primary);
-      margin: 0;
-      flex: 1;
-    }}
-
-    .onion-url-box {{
-      background: var(--tor-purple-800);
-      border: 1px solid var(--tor-purple-600);
-      border-radius: 6px;
-      padding: 12px;
-    }}
-
-    .onion-url-content {{
-      display: flex;
-      justify-content: space-between;
-      align-items: center;
-      gap: 8px;
-    }}
-
-    .onion-url-left {{
-      flex: 1;
-      min-width: 0;
-      display: flex;
-      align-items: center;
-      gap: 8px;
-    }}
-
-    .onion-icon {{
-      width: 16px;
-      height: 16px;
-      flex-shrink: 0;
-    }}
-
-    .onion-label {{
-      font-size: 0.75rem;
-      color: var(--tor-purple-200);
-      text-transform: uppercase;
-      letter-spacing: 0.5px;
-      font-weight: 600;
-      flex-shrink: 0;
-    }}
-
-    .onion-url {{
-      font-family: 'Space Mono', monospace;
-      font-size: 0.75rem;
-      color: var(--tor-purple-200);
-      word-break: break-all;
-      line-height: 1.4;
-      margin-top: 8px;
-    }}
-
-    .card-meta {{
-      display: flex;
-      align-items: center;
-      justify-content: space-between;
-      font-size: 0.875rem;
-      color: var(--text-secondary);
-      gap: 8px;
-    }}
-
-    .meta-item {{
-      display: flex;
-      align-items: center;
-      gap: 4px;
-    }}
-
-    .meta-label {{
-      color: var(--text-secondary);
-    }}
-
-    .meta-value {{
-      color: var(--text-primary);
-      font-weight: 500;
-    }}
-
-    .status-badge {{
-      display: inline-flex;
-      align-items: center;
-      padding: 2px 8px;
-      border-radius: 12px;
-      font-size: 0.75rem;
-      font-weight: 600;
-      text-transform: uppercase;
-      letter-spacing: 0.025em;
-      flex-shrink: 0;
-    }}
-
-    .status-online {{
-      background: rgba(72, 187, 120, 0.2);
-      color: #48BB78;
-    }}
-
-    .status-offline {{
-      background: rgba(245, 101, 101, 0.2);
-      color: #F56565;
-    }}
-
-    .status-unknown {{
-      background: rgba(160, 174, 192, 0.2);
-      color: #A0AEC0;
-    }}
-
-    .status-error {{
-      background: rgba(237, 137, 54, 0.2);
-      color: #ED8936;
-    }}
-
-    footer {{
-      width: 100%;
-      background: var(--bg-card);
-      border-top: 1px solid var(--border-color);
-      margin-top: auto;
-    }}
-
-    .footer-container {{
-      max-width: 100%;
-      margin: 0 auto;
-      padding: 32px 16px;
-    }}
-
-    @media (min-width: 768px) {{
-      .footer-container {{
-        padding: 32px 32px;
-      }}
-    }}
-
-    @media (min-width: 1024px) {{
-      .footer-container {{
-        padding: 32px 48px;
-      }}
-    }}
-
-    .footer-content {{
-      display: flex;
-      justify-content: center;
-      align-items: center;
-      flex-wrap: wrap;
-      gap: 16px;
-      font-size: 0.875rem;
-      color: var(--text-secondary);
-    }}
-
-    .footer-content a {{
-      color: var(--text-secondary);
-      text-decoration: none;
-      display: inline-flex;
-      align-items: center;
-      gap: 8px;
-      transition: color 0.2s;
-    }}
-
-    .footer-content a:hover {{
-      color: var(--tor-purple-500);
-      text-decoration: underline;
-    }}
-
-    .footer-divider {{
-      color: var(--text-secondary);
-      opacity: 0.4;
-    }}
-
-    @media (max-width: 768px) {{
-      .cards-grid {{
-        grid-template-columns: 1fr;
-        gap: 16px;
-      }}
-
-      header {{
-        padding: 16px;
-        flex-direction: column;
-        align-items: flex-start;
-        gap: 16px;
-      }}
-
-      .header-content {{
-        gap: 12px;
-      }}
-
-      .card-content {{
-        padding: 12px;
-      }}
-    }}
-  </style>
-</head>
-<body>
-  <div class="page-wrapper">
-    <header>
-      <div class="header-content">
-        <svg class="tor-logo" xmlns="http://www.w3.org/2000/svg" viewBox="0 0 100 100">
-          <circle cx="50" cy="50" r="45" fill="#7d33b8"/>
-          <circle cx="50" cy="50" r="35" fill="none" stroke="white" stroke-width="3" opacity="0.8"/>
-          <circle cx="50" cy="50" r="25" fill="none" stroke="white" stroke-width="3" opacity="0.6"/>
-          <circle cx="50" cy="50" r="15" fill="none" stroke="white" stroke-width="3" opacity="0.4"/>
-          <circle cx="50" cy="50" r="6" fill="white"/>
-        </svg>
-        <div class="header-text">
-          <h1>Onion Service Monitor</h1>
-          <p class="subtitle">Monitoring Tor Hidden Services via Arti</p>
-        </div>
-      </div>
-    </header>
-
-    <div class="content-wrapper">
-      <div class="container">
-        <div class="section-header">
-          <h2 class="section-title">Monitored Services</h2>
-        </div>
-
-        <div class="cards-grid">
-{}
-        </div>
-      </div>
-    </div>
-
-    <footer>
-      <div class="footer-container">
-        <div class="footer-content">
-          <span>Powered by Arti - The Rust Tor Implementation</span>
-          <span class="footer-divider">•</span>
-          <span>Last updated: {}</span>
-          <span class="footer-divider">•</span>
-          <a href="https://github.com/igor53627/tor-ethereum-ecosystem" target="_blank">
-            <svg width="20" height="20" viewBox="0 0 24 24" fill="currentColor">
-              <path d="M12 0c-6.626 0-12 5.373-12 12 0 5.302 3.438 9.8 8.207 11.387.599.111.793-.261.793-.577v-2.234c-3.338.726-4.033-1.416-4.033-1.416-.546-1.387-1.333-1.756-1.333-1.756-1.089-.745.083-.729.083-.729 1.205.084 1.839 1.237 1.839 1.237 1.07 1.834 2.807 1.304 3.492.997.107-.775.418-1.305.762-1.604-2.665-.305-5.467-1.334-5.467-5.931 0-1.311.469-2.381 1.236-3.221-.124-.303-.535-1.524.117-3.176 0 0 1.008-.322 3.301 1.23.957-.266 1.983-.399 3.003-.404 1.02.005 2.047.138 3.006.404 2.291-1.552 3.297-1.23 3.297-1.23.653 1.653.242 2.874.118 3.176.77.84 1.235 1.911 1.235 3.221 0 4.609-2.807 5.624-5.479 5.921.43.372.823 1.102.823 2.222v3.293c0 .319.192.694.801.576 4.765-1.589 8.199-6.086 8.199-11.386 0-6.627-5.373-12-12-12z"/>
-            </svg>
-            Tor in Ethereum Ecosystem
-          </a>
-        </div>
-      </div>
-    </footer>
-  </div>
-</body>
-</html>"##,
-        cards,
-        now
-    )
+    Ok(())
 }
 
+const USAGE: &str = "Usage:
+  onion-monitor [run]                  Check all sites and regenerate the outputs
+  onion-monitor render                 Regenerate the static site from the saved data, without checking
+  onion-monitor schema [KIND]          Print the JSON Schema of a data file (default: sites)
+  onion-monitor validate FILE [KIND]   Validate FILE against its JSON Schema (default: sites)";
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match args.first().map(String::as_str) {
+        None | Some("run") => run().await,
+        Some("render") => render_saved(),
+        Some("schema") => print_schema(&args[1..]),
+        Some("validate") => validate_file(&args[1..]),
+        Some("help") | Some("--help") | Some("-h") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some(other) => bail!("Unknown command '{}'\n\n{}", other, USAGE),
+    }
+}
+
+fn print_schema(args: &[String]) -> Result<()> {
+    let kind = match args.first() {
+        Some(name) => schema::SchemaKind::parse(name)?,
+        None => schema::SchemaKind::Sites,
+    };
+
+    println!("{}", serde_json::to_string_pretty(&schema::schema_for(kind))?);
+    Ok(())
+}
+
+fn validate_file(args: &[String]) -> Result<()> {
+    let Some(path) = args.first() else {
+        bail!("Missing FILE\n\n{}", USAGE);
+    };
+    let kind = match args.get(1) {
+        Some(name) => schema::SchemaKind::parse(name)?,
+        None => schema::SchemaKind::Sites,
+    };
+
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path))?;
+    let instance: serde_json::Value = serde_json::from_str(&contents)
+        .with_context(|| format!("{} is not valid JSON", path))?;
+
+    let errors = schema::validate(kind, &instance)
+        .with_context(|| format!("Cannot validate {}", path))?;
+    if errors.is_empty() {
+        println!("✓ {} is a valid {} file", path, kind.name());
+        return Ok(());
+    }
+
+    for error in &errors {
+        println!("  ✗ {}", error);
+    }
+    bail!("{} failed validation with {} error(s)", path, errors.len())
+}
+
+/// Directory holding onions.json, history.json and docs/ (a mounted directory, so files
+/// can be replaced atomically)
+fn data_dir() -> PathBuf {
+    PathBuf::from(env::var("DATA_DIR").unwrap_or_else(|_| "/app".to_string()))
+}
+
+/// Read the sites document, if it exists. A file that exists but does not parse is a
+/// hard error: carrying on would overwrite every site's history with an empty list.
+fn load_sites(path: &Path) -> Result<Option<document::SitesDocument>> {
+    let Some(json_content) = store::read_optional(path)? else {
+        return Ok(None);
+    };
+    let mut document = document::SitesDocument::parse(&json_content).with_context(|| format!(
+        "Failed to parse {} (the previous version is kept in {})",
+        path.display(),
+        store::backup_path(path).display()
+    ))?;
+    for site in &mut document.sites {
+        site.ensure_endpoints();
+    }
+    Ok(Some(document))
+}
+
+fn load_history(path: &Path) -> Result<history::HistoryDocument> {
+    match store::read_optional(path)? {
+        Some(json_content) => history::HistoryDocument::parse(&json_content).with_context(|| format!(
+            "Failed to parse {} (the previous version is kept in {})",
+            path.display(),
+            store::backup_path(path).display()
+        )),
+        None => Ok(history::HistoryDocument::default()),
+    }
+}
+
+/// Regenerate docs/ from onions.json and history.json as left by the last run
+fn render_saved() -> Result<()> {
+    let data_dir = data_dir();
+    let json_path = data_dir.join("onions.json");
+    let Some(document) = load_sites(&json_path)? else {
+        bail!("{} does not exist; run a check first", json_path.display());
+    };
+    let history = load_history(&data_dir.join("history.json"))?;
+    let renderer = render::Renderer::from_env()?;
+
+    let generated_at = document.run.as_ref()
+        .and_then(|run| run.finished_at)
+        .unwrap_or_else(Utc::now);
+    let results: Vec<CheckResult> = document.sites.into_iter().map(CheckResult::from).collect();
+
+    let pages = renderer.render_site(&results, &history, generated_at)?;
+    render::write_site(&data_dir.join("docs"), &pages)?;
+
+    println!("✓ Generated docs/ ({} pages)", pages.len());
+    Ok(())
+}
+
+async fn run() -> Result<()> {
     println!("🧅 Onion Service Monitor - Starting...\n");
+    let started_at = Utc::now();
 
-    // Get proxy URL from environment
-    let proxy_url = env::var("SOCKS_PROXY")
-        .unwrap_or_else(|_| "socks5://127.0.0.1:9150".to_string());
+    // SOCKS proxies (or the embedded Arti client) to check through; the first one is also
+    // used for verification
+    let vantages = vantage::Vantage::from_env()?;
+    for vantage in &vantages {
+        vantage.check_ready().await?;
+    }
+    let primary = &vantages[0];
 
-    println!("Using SOCKS proxy: {}", proxy_url);
+    let data_dir = data_dir();
+    let json_path = data_dir.join("onions.json");
+    let history_path = data_dir.join("history.json");
+    let docs_dir = data_dir.join("docs");
+    let output_config = output::OutputConfig::from_env();
+    let renderer = render::Renderer::from_env()?;
+
+    // Read existing sites (if file exists)
+    let existing_sites = load_sites(&json_path)?.map(|doc| doc.sites).unwrap_or_default();
+    let mut history = load_history(&history_path)?;
 
     // Update onion addresses from GitHub
-    let json_path = "/app/onions.json";
     let github_sites = fetch_onion_addresses_from_github().await?;
 
-    // Read existing sites (if file exists)
-    let existing_sites: Vec<OnionSite> = if let Ok(json_content) = fs::read_to_string(json_path) {
-        serde_json::from_str(&json_content).unwrap_or_default()
-    } else {
-        Vec::new()
-    };
-
     // Merge GitHub sites with existing sites
     let sites = merge_onion_sites(github_sites, existing_sites);
 
-    // Save merged sites
-    let merged_json = serde_json::to_string_pretty(&sites)?;
-    fs::write(json_path, merged_json)
+    // Save merged sites, keeping the previous run's file as the backup
+    let mut run = document::RunMetadata::new(started_at, &primary.transport.to_string());
+    if vantages.len() > 1 {
+        run.vantages = vantages.iter().map(|v| v.name.clone()).collect();
+    }
+    let merged_json = serde_json::to_string_pretty(&document::SitesDocument::new(run.clone(), sites.clone()))?;
+    store::write_with_backup(&json_path, merged_json.as_bytes())
         .context("Failed to write merged onions.json")?;
 
     println!("✓ Updated onions.json with {} sites\n", sites.len());
 
-    // Parse proxy URL to extract host and port
-    let proxy_parts: Vec<&str> = proxy_url.trim_start_matches("socks5://").split(':').collect();
-    let proxy_host = proxy_parts.get(0).unwrap_or(&"arti");
-    let proxy_port: u16 = proxy_parts.get(1).and_then(|p| p.parse().ok()).unwrap_or(9150);
+    let options = CheckOptions::from_env();
+    let mut control_config = control::ControlConfig::from_env();
+    if control_config.is_some() && primary.transport.socks().is_none() {
+        println!("⚠ Warning: TOR_CONTROL is ignored with the embedded Arti client, which diagnoses failures itself\n");
+        control_config = None;
+    }
+    let https = sites.iter().flat_map(|s| &s.endpoints).any(|e| e.url.starts_with("https://"));
+    if https && primary.transport.socks().is_none() {
+        println!("⚠ Warning: the embedded Arti client does not inspect TLS certificates; HTTPS endpoints get no certificate details\n");
+    }
+    if let Some(config) = &control_config {
+        println!("Checking descriptors and diagnosing failures via the Tor control port at {}\n", config.address);
+    }
 
-    println!("Using SOCKS5h proxy: {}:{}\n", proxy_host, proxy_port);
+    let client_auth = clientauth::ClientAuthKeys::from_env(&sites)
+        .context("Failed to load CLIENT_AUTH_KEYS")?;
+    if !client_auth.is_empty() {
+        match &control_config {
+            Some(config) => {
+                control::add_client_auth(config, &client_auth).await
+                    .context("Failed to add client authorization keys via the Tor control port")?;
+                println!("Added client authorization keys for {} onion services\n", client_auth.len());
+            }
+            None if primary.transport.socks().is_none() => println!(
+                "⚠ Warning: CLIENT_AUTH_KEYS is not supported with the embedded Arti client; \
+                 services needing client authorization will fail at the descriptor\n"
+            ),
+            None => println!(
+                "⚠ Warning: CLIENT_AUTH_KEYS needs TOR_CONTROL to hand the keys to Tor; \
+                 otherwise configure them in the proxy itself\n"
+            ),
+        }
+    }
 
-    // Check all sites using curl with SOCKS5h
-    let mut results = Vec::new();
+    // Check all sites using curl with SOCKS5h, or the embedded Arti client
+    let mut results: Vec<CheckResult> = Vec::new();
     for site in sites {
-        let result = check_onion_site_curl(&site, proxy_host, proxy_port).await;
+        let result = check_site(&site, &vantages, &options, control_config.as_ref(), &client_auth).await;
         results.push(result);
 
         // Small delay between checks to be nice
@@ -791,38 +1138,198 @@ async fn main() -> Result<()> {
 
     println!("\n📊 Check complete!");
 
+    verify_sites(&mut results, primary.transport.socks()).await?;
+    if let Some(config) = &control_config {
+        check_descriptors(&mut results, config, &client_auth).await;
+    }
+
     // Save updated JSON
     let updated_sites: Vec<OnionSite> = results.iter()
         .map(|r| r.site.clone())
         .collect();
 
-    let updated_json = serde_json::to_string_pretty(&updated_sites)
+    let now = Utc::now();
+    run.finished_at = Some(now);
+    let updated_document = document::SitesDocument::new(run, updated_sites);
+    let updated_json = serde_json::to_string_pretty(&updated_document)
         .context("Failed to serialize results")?;
 
-    fs::write(json_path, updated_json)
+    // The backup was taken before the first write and keeps the previous run's data
+    store::write_atomic(&json_path, updated_json.as_bytes())
         .context("Failed to write updated onions.json")?;
 
     println!("✓ Updated onions.json");
 
-    // Generate HTML
-    let html = generate_html(&results);
+    // Append this run to the check history
+    history.record(&updated_document.sites, now);
+    let history_json = serde_json::to_string_pretty(&history)
+        .context("Failed to serialize history")?;
+    store::write_with_backup(&history_path, history_json.as_bytes())
+        .context("Failed to write history.json")?;
 
-    fs::create_dir_all("/app/docs")
-        .context("Failed to create docs directory")?;
+    println!("✓ Updated history.json");
 
-    fs::write("/app/docs/index.html", html)
-        .context("Failed to write index.html")?;
+    output::write_outputs(&output_config, &updated_document, &history, now)?;
 
-    println!("✓ Generated docs/index.html");
+    // Generate the static site
+    let pages = renderer.render_site(&results, &history, now)?;
+    render::write_site(&docs_dir, &pages)?;
+
+    println!("✓ Generated docs/ ({} pages)", pages.len());
 
     // Print summary
     let online_count = results.iter().filter(|r| r.site.status == "online").count();
+    let partial_count = results.iter().filter(|r| r.site.status == "partial").count();
     let offline_count = results.iter().filter(|r| r.site.status == "offline").count();
 
     println!("\n📈 Summary:");
     println!("   Online:  {}", online_count);
+    println!("   Partial: {}", partial_count);
     println!("   Offline: {}", offline_count);
     println!("   Total:   {}", results.len());
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoints(statuses: &[&str]) -> Vec<Endpoint> {
+        statuses.iter().enumerate().map(|(i, status)| {
+            let mut endpoint = Endpoint::new(format!("http://site{}.onion", i), None);
+            endpoint.status = status.to_string();
+            endpoint
+        }).collect()
+    }
+
+    #[test]
+    fn aggregates_endpoint_statuses() {
+        assert_eq!(aggregate_status(&endpoints(&["online", "online"])), "online");
+        assert_eq!(aggregate_status(&endpoints(&["online", "offline"])), "partial");
+        // Reached through some vantage points only
+        assert_eq!(aggregate_status(&endpoints(&["partial", "offline"])), "partial");
+        assert_eq!(aggregate_status(&endpoints(&["partial", "error-502"])), "partial");
+        assert_eq!(aggregate_status(&endpoints(&["partial"])), "partial");
+        assert_eq!(aggregate_status(&endpoints(&["error-502", "error-502"])), "error-502");
+        assert_eq!(aggregate_status(&endpoints(&["offline", "error-503"])), "offline");
+        assert_eq!(aggregate_status(&[]), "unknown");
+    }
+
+    fn site(title: &str, name: &str, addresses: &[&str]) -> OnionSite {
+        OnionSite {
+            title: title.to_string(),
+            name: name.to_string(),
+            onion_address: addresses[0].to_string(),
+            status: "unknown".to_string(),
+            prev_status: "unknown".to_string(),
+            last_checked: None,
+            endpoints: addresses.iter().map(|a| Endpoint::new(a.to_string(), None)).collect(),
+            metadata: ProjectMetadata::default(),
+            extra: BTreeMap::new(),
+            onion_location: None,
+            ownership: None,
+        }
+    }
+
+    #[test]
+    fn matches_renamed_and_moved_sites() {
+        let mut wallet = site("Wallet", "wallet", &["http://wallet.onion", "http://mirror.onion"]);
+        wallet.status = "online".to_string();
+        wallet.endpoints[1].status = "offline".to_string();
+        let mut bridge = site("Bridge", "bridge", &["http://bridge.onion"]);
+        bridge.status = "partial".to_string();
+
+        let merged = merge_onion_sites(
+            vec![
+                // Renamed upstream, matched by a mirror address
+                site("Wallet Pro", "wallet-pro", &["http://new.onion", "http://MIRROR.onion/"]),
+                // Moved to a new address, matched by name
+                site("Bridge", "bridge", &["http://bridge-v3.onion"]),
+                // A new project whose slug is taken
+                site("Bridge Two", "wallet", &["http://other.onion"]),
+            ],
+            vec![wallet, bridge],
+        );
+
+        let names: Vec<(&str, &str, &str)> = merged.iter().map(|s| (s.title.as_str(), s.name.as_str(), s.status.as_str())).collect();
+        assert_eq!(names, [("Bridge", "bridge", "partial"), ("Bridge Two", "wallet-2", "unknown"), ("Wallet Pro", "wallet", "online")]);
+
+        let wallet = &merged[2];
+        assert_eq!(wallet.onion_address, "http://new.onion");
+        assert_eq!(wallet.endpoints.iter().map(|e| e.status.as_str()).collect::<Vec<_>>(), ["unknown", "offline"]);
+        assert_eq!(merged[0].endpoints[0].url, "http://bridge-v3.onion");
+    }
+
+    #[test]
+    fn refuses_to_load_unreadable_sites() {
+        let dir = std::env::temp_dir().join(format!("onion-monitor-load-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("onions.json");
+
+        assert!(load_sites(&path).unwrap().is_none());
+
+        fs::write(&path, "[{\"title\": \"Wallet\",").unwrap();
+        let error = format!("{:#}", load_sites(&path).unwrap_err());
+        assert!(error.contains("onions.json.bak"), "{}", error);
+
+        fs::write(&path, "[]").unwrap();
+        assert!(load_sites(&path).unwrap().unwrap().sites.is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_projects_with_mistyped_fields() {
+        let text = r#"[
+            { "name": "Wallet", "onion": "wallet.onion", "tags": "defi", "category": "Wallets" },
+            { "name": "Bridge", "onion": ["a.onion", { "url": "b.onion", "label": "API" }], "id": 7 },
+            "not a project",
+            { "name": "Explorer", "onion": "explorer.onion", "license": "MIT" }
+        ]"#;
+        let projects = parse_projects(text, "ecosystem.json");
+        assert_eq!(projects.len(), 3);
+
+        let wallet = &projects[0];
+        assert!(wallet.metadata.tags.is_empty());
+        assert_eq!(wallet.metadata.category.as_deref(), Some("Wallets"));
+        assert_eq!(wallet.extra["tags"], "defi");
+
+        let bridge = &projects[1];
+        assert_eq!(bridge.id, None);
+        assert_eq!(bridge.extra["id"], 7);
+        assert_eq!(bridge.endpoints().len(), 2);
+
+        assert_eq!(projects[2].extra["license"], "MIT");
+        assert!(parse_projects("{}", "broken.json").is_empty());
+    }
+
+    #[test]
+    fn reloads_mistyped_fields_into_extra() {
+        let (project, _) = GitHubProject::from_value(serde_json::json!({
+            "name": "Wallet", "onion": "wallet.onion", "tags": "defi"
+        })).unwrap();
+        let mut site = OnionSite {
+            title: project.name.clone(),
+            name: "wallet".to_string(),
+            onion_address: "http://wallet.onion".to_string(),
+            status: "online".to_string(),
+            prev_status: "online".to_string(),
+            last_checked: None,
+            endpoints: project.endpoints(),
+            metadata: project.metadata,
+            extra: project.extra,
+            onion_location: None,
+            ownership: None,
+        };
+        site.ensure_endpoints();
+
+        let json = serde_json::to_string(&document::SitesDocument::new(
+            document::RunMetadata::new(Utc::now(), "socks5h://127.0.0.1:9150"),
+            vec![site],
+        )).unwrap();
+        assert!(json.contains(r#""tags":"defi""#));
+        let reloaded = document::SitesDocument::parse(&json).unwrap();
+        assert_eq!(reloaded.sites[0].extra["tags"], "defi");
+        assert!(reloaded.sites[0].metadata.tags.is_empty());
+    }
+}
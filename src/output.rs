@@ -0,0 +1,207 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::document::{RunMetadata, SitesDocument, SCHEMA_VERSION};
+use crate::history::{self, HistoryDocument, Incident};
+use crate::store;
+use crate::OnionSite;
+
+/// Where the generated data files go, besides `DATA_DIR/onions.json`
+#[derive(Debug, Default)]
+pub struct OutputConfig {
+    /// Extra copies of the sites document, from `SITES_OUTPUTS`
+    pub sites_paths: Vec<PathBuf>,
+    /// Frontend-optimised documents, from `FRONTEND_OUTPUTS`
+    pub frontend_paths: Vec<PathBuf>,
+}
+
+impl OutputConfig {
+    pub fn from_env() -> Self {
+        OutputConfig {
+            sites_paths: paths_from_env("SITES_OUTPUTS"),
+            frontend_paths: paths_from_env("FRONTEND_OUTPUTS"),
+        }
+    }
+}
+
+/// Parse a comma-separated list of paths
+fn paths_from_env(name: &str) -> Vec<PathBuf> {
+    std::env::var(name)
+        .map(|value| value.split(',')
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from)
+            .collect())
+        .unwrap_or_default()
+}
+
+/// `onions.json` enriched with fields the frontend would otherwise compute itself
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct FrontendDocument {
+    pub schema_version: u32,
+    pub generated_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run: Option<RunMetadata>,
+    pub sites: Vec<FrontendSite>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct FrontendSite {
+    #[serde(flatten)]
+    pub site: OnionSite,
+    pub uptime: Uptime,
+    /// Time of the most recent status change
+    pub last_change: Option<DateTime<Utc>>,
+    pub incidents: IncidentSummary,
+}
+
+/// Availability percentages; `None` when there are no checks in the window
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Uptime {
+    pub day: Option<f64>,
+    pub week: Option<f64>,
+    pub month: Option<f64>,
+    pub quarter: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct IncidentSummary {
+    /// Incidents that started within the last 30 days
+    pub last_30_days: usize,
+    pub ongoing: Option<Incident>,
+    /// The most recent incident that has ended
+    pub last_resolved: Option<Incident>,
+}
+
+impl FrontendDocument {
+    pub fn new(document: &SitesDocument, history: &HistoryDocument, now: DateTime<Utc>) -> Self {
+        let sites = document.sites.iter()
+            .map(|site| {
+                let records = history.for_site(&site.name);
+                let incidents = history::incidents(records);
+
+                FrontendSite {
+                    site: site.clone(),
+                    uptime: Uptime {
+                        day: history::uptime(records, now - Duration::days(1)),
+                        week: history::uptime(records, now - Duration::days(7)),
+                        month: history::uptime(records, now - Duration::days(30)),
+                        quarter: history::uptime(records, now - Duration::days(90)),
+                    },
+                    last_change: history::last_change(records),
+                    incidents: IncidentSummary {
+                        last_30_days: incidents.iter()
+                            .filter(|i| i.started_at >= now - Duration::days(30))
+                            .count(),
+                        ongoing: incidents.last().filter(|i| i.ended_at.is_none()).cloned(),
+                        last_resolved: incidents.iter().rev().find(|i| i.ended_at.is_some()).cloned(),
+                    },
+                }
+            })
+            .collect();
+
+        FrontendDocument {
+            schema_version: SCHEMA_VERSION,
+            generated_at: now,
+            run: document.run.clone(),
+            sites,
+        }
+    }
+}
+
+/// Write `contents` to `path`, creating parent directories as needed
+fn write_output(path: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    store::write_atomic(path, contents)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Write the sites document and frontend variant to every configured extra target
+pub fn write_outputs(
+    config: &OutputConfig,
+    document: &SitesDocument,
+    history: &HistoryDocument,
+    now: DateTime<Utc>,
+) -> Result<()> {
+    if !config.sites_paths.is_empty() {
+        let json = serde_json::to_string_pretty(document)?;
+        for path in &config.sites_paths {
+            write_output(path, json.as_bytes())?;
+            println!("✓ Wrote {}", path.display());
+        }
+    }
+
+    if !config.frontend_paths.is_empty() {
+        let json = serde_json::to_string_pretty(&FrontendDocument::new(document, history, now))?;
+        for path in &config.frontend_paths {
+            write_output(path, json.as_bytes())?;
+            println!("✓ Wrote {}", path.display());
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::CheckRecord;
+
+    #[test]
+    fn computes_frontend_fields_from_history() {
+        let now = Utc::now();
+        let site: OnionSite = serde_json::from_value(serde_json::json!({
+            "title": "Example Wallet",
+            "name": "example-wallet",
+            "onion_address": "http://wallet.onion",
+            "status": "offline",
+            "prev_status": "online",
+            "last_checked": now,
+        })).unwrap();
+        let record = |days: i64, status: &str| CheckRecord {
+            checked_at: now - Duration::days(days),
+            status: status.to_string(),
+            response_time_ms: None,
+            timings: None,
+            content_change: None,
+        };
+        let mut history = HistoryDocument::default();
+        history.sites.insert("example-wallet".to_string(), vec![
+            record(60, "offline"),
+            record(45, "online"),
+            record(20, "offline"),
+            record(10, "online"),
+            record(0, "offline"),
+        ]);
+        let document = SitesDocument {
+            schema_version: SCHEMA_VERSION,
+            run: None,
+            sites: vec![site],
+        };
+
+        let frontend = FrontendDocument::new(&document, &history, now);
+        let site = &frontend.sites[0];
+        assert_eq!(site.uptime.day, Some(0.0));
+        assert_eq!(site.uptime.week, Some(0.0));
+        assert_eq!(site.uptime.month, Some(1.0 / 3.0 * 100.0));
+        assert_eq!(site.uptime.quarter, Some(40.0));
+        assert_eq!(site.last_change, Some(now));
+
+        // The incident from 60 days ago is outside the 30-day count
+        assert_eq!(site.incidents.last_30_days, 2);
+        let ongoing = site.incidents.ongoing.as_ref().unwrap();
+        assert_eq!((ongoing.started_at, ongoing.ended_at), (now, None));
+        let resolved = site.incidents.last_resolved.as_ref().unwrap();
+        assert_eq!(resolved.started_at, now - Duration::days(20));
+        assert_eq!(resolved.ended_at, Some(now - Duration::days(10)));
+
+        assert!(FrontendDocument::new(&document, &HistoryDocument::default(), now).sites[0].uptime.quarter.is_none());
+    }
+}
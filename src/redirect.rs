@@ -0,0 +1,135 @@
+use reqwest::Url;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Redirects followed per check, unless overridden by `MAX_REDIRECTS`
+const DEFAULT_MAX_REDIRECTS: usize = 5;
+
+/// One redirect response
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq, Eq)]
+pub struct Redirect {
+    pub url: String,
+    pub status_code: u16,
+    /// Absolute target of the `Location` header
+    pub location: String,
+}
+
+/// Where an endpoint redirects to. Redirects are followed only between onion services, so the
+/// monitor never requests a clearnet URL on a service's behalf.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct RedirectCheck {
+    /// Redirect responses in the order they were received
+    pub chain: Vec<Redirect>,
+    /// Where the chain ends: the page whose status counts, or the first URL not followed
+    pub final_url: String,
+    /// Whether a redirect points away from onion services (a privacy problem)
+    pub leaves_onion: bool,
+    /// Whether the chain points back to a URL already requested
+    pub looped: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// Redirects to follow per check, from `MAX_REDIRECTS`; 0 records the first one only
+pub fn max_redirects_from_env() -> usize {
+    match std::env::var("MAX_REDIRECTS") {
+        Ok(value) => value.trim().parse().unwrap_or_else(|_| {
+            println!("⚠ Warning: Invalid MAX_REDIRECTS '{}', using {}", value, DEFAULT_MAX_REDIRECTS);
+            DEFAULT_MAX_REDIRECTS
+        }),
+        Err(_) => DEFAULT_MAX_REDIRECTS,
+    }
+}
+
+impl RedirectCheck {
+    pub fn new(url: &str) -> Self {
+        RedirectCheck {
+            chain: Vec::new(),
+            final_url: url.to_string(),
+            leaves_onion: false,
+            looped: false,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Record a redirect from `url` and decide whether to follow it: `Some(next URL)` unless it
+    /// leaves the onion, loops, or exceeds `max_redirects`
+    pub fn record(&mut self, url: &str, status_code: u16, location: &str, max_redirects: usize) -> Option<String> {
+        let requested: Vec<&str> = std::iter::once(url).chain(self.chain.iter().map(|r| r.url.as_str())).collect();
+        let looped = requested.iter().any(|r| same_url(r, location));
+
+        self.chain.push(Redirect { url: url.to_string(), status_code, location: location.to_string() });
+        self.final_url = location.to_string();
+
+        if !is_onion(location) {
+            self.leaves_onion = true;
+            self.warnings.push(format!("Redirects off the onion to {}", location));
+            None
+        } else if looped {
+            self.looped = true;
+            self.warnings.push(format!("Redirect loop back to {}", location));
+            None
+        } else if self.chain.len() > max_redirects {
+            if max_redirects > 0 {
+                self.warnings.push(format!("More than {} redirects", max_redirects));
+            }
+            None
+        } else {
+            Some(location.to_string())
+        }
+    }
+}
+
+fn is_onion(url: &str) -> bool {
+    Url::parse(url).ok()
+        .and_then(|u| u.host_str().map(|h| h.trim_end_matches('.').to_ascii_lowercase()))
+        .is_some_and(|host| host.ends_with(".onion"))
+}
+
+fn same_url(a: &str, b: &str) -> bool {
+    match (Url::parse(a), Url::parse(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_redirects_within_onions() {
+        let mut check = RedirectCheck::new("http://abc.onion");
+        assert_eq!(check.record("http://abc.onion", 301, "https://abc.onion/", 5).as_deref(), Some("https://abc.onion/"));
+        assert_eq!(check.record("https://abc.onion/", 302, "https://xyz.onion/home", 5).as_deref(), Some("https://xyz.onion/home"));
+        assert_eq!(check.final_url, "https://xyz.onion/home");
+        assert!(check.warnings.is_empty());
+
+        // With following disabled, the first redirect is still recorded
+        let mut check = RedirectCheck::new("http://abc.onion");
+        assert_eq!(check.record("http://abc.onion", 301, "https://abc.onion/", 0), None);
+        assert_eq!(check.chain.len(), 1);
+        assert!(check.warnings.is_empty());
+    }
+
+    #[test]
+    fn stops_at_clearnet_and_loops() {
+        let mut check = RedirectCheck::new("http://abc.onion/");
+        assert_eq!(check.record("http://abc.onion/", 302, "https://example.com/", 5), None);
+        assert!(check.leaves_onion);
+        assert_eq!(check.warnings, ["Redirects off the onion to https://example.com/"]);
+
+        let mut check = RedirectCheck::new("http://abc.onion");
+        assert!(check.record("http://abc.onion", 301, "http://abc.onion/a", 5).is_some());
+        assert_eq!(check.record("http://abc.onion/a", 301, "http://abc.onion/", 5), None);
+        assert!(check.looped);
+        assert_eq!(check.warnings, ["Redirect loop back to http://abc.onion/"]);
+
+        let mut check = RedirectCheck::new("http://abc.onion/0");
+        for i in 0..2 {
+            assert!(check.record(&format!("http://abc.onion/{}", i), 302, &format!("http://abc.onion/{}", i + 1), 2).is_some());
+        }
+        assert_eq!(check.record("http://abc.onion/2", 302, "http://abc.onion/3", 2), None);
+        assert_eq!(check.warnings, ["More than 2 redirects"]);
+    }
+}
@@ -0,0 +1,1338 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use minijinja::{AutoEscape, Environment, Value};
+use reqwest::Url;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::control::Diagnosis;
+use crate::fingerprint::ContentFingerprint;
+use crate::freshness::DescriptorCheck;
+use crate::history::{self, CheckRecord, HistoryDocument, Incident};
+use crate::redirect::RedirectCheck;
+use crate::timing::Timings;
+use crate::tls::CertificateCheck;
+use crate::verify::{ProofMethod, VerificationStatus};
+use crate::{slug, store, svg};
+use crate::{CheckResult, Endpoint, OnionSite};
+
+/// Templates compiled into the binary; a `TEMPLATE_DIR` may override any of them
+const EMBEDDED_TEMPLATES: &[(&str, &str)] = &[
+    ("base.html", include_str!("../templates/base.html")),
+    ("index.html", include_str!("../templates/index.html")),
+    ("service.html", include_str!("../templates/service.html")),
+    ("card.html", include_str!("../templates/card.html")),
+    ("style.css", include_str!("../templates/style.css")),
+    ("atom.xml", include_str!("../templates/atom.xml")),
+    ("rss.xml", include_str!("../templates/rss.xml")),
+];
+
+const DATE_FORMAT: &str = "%b %d, %Y %H:%M UTC";
+
+/// Directory under the output root holding one page per service
+const SERVICES_DIR: &str = "services";
+
+/// Most recent checks listed in a service's history table
+const MAX_HISTORY_ROWS: usize = 100;
+
+const CHART_WIDTH: u32 = 720;
+const CHART_HEIGHT: u32 = 200;
+
+/// Days covered by the uptime bar on each card, one segment per day
+const UPTIME_BAR_DAYS: u32 = 90;
+/// Checks covered by the latency sparkline on each card
+const SPARKLINE_CHECKS: usize = 50;
+
+/// Most recent events listed in the feeds
+const MAX_FEED_ENTRIES: usize = 50;
+
+/// Feeds of status changes published next to the index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedFormat {
+    Atom,
+    Rss,
+}
+
+impl FeedFormat {
+    const ALL: &'static [FeedFormat] = &[FeedFormat::Atom, FeedFormat::Rss];
+
+    /// Feeds listed in `FEEDS` (comma-separated), Atom only by default
+    pub fn from_env() -> Vec<FeedFormat> {
+        let Ok(value) = std::env::var("FEEDS") else {
+            return vec![FeedFormat::Atom];
+        };
+
+        value.split(',')
+            .map(str::trim)
+            .filter(|f| !f.is_empty())
+            .filter_map(|f| match f {
+                "atom" => Some(FeedFormat::Atom),
+                "rss" => Some(FeedFormat::Rss),
+                other => {
+                    println!("⚠ Warning: Unknown feed format '{}'", other);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Output file, which is also the name of its template
+    fn file_name(self) -> &'static str {
+        match self {
+            FeedFormat::Atom => "atom.xml",
+            FeedFormat::Rss => "rss.xml",
+        }
+    }
+
+    fn mime_type(self) -> &'static str {
+        match self {
+            FeedFormat::Atom => "application/atom+xml",
+            FeedFormat::Rss => "application/rss+xml",
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            FeedFormat::Atom => "Atom feed",
+            FeedFormat::Rss => "RSS feed",
+        }
+    }
+}
+
+pub struct Renderer {
+    env: Environment<'static>,
+    feeds: Vec<FeedFormat>,
+    /// Public URL of the site, to make feed links absolute
+    site_url: Option<String>,
+}
+
+impl Renderer {
+    /// Build a renderer using `TEMPLATE_DIR` (if set) in front of the embedded templates,
+    /// publishing the `FEEDS` under `SITE_URL`
+    pub fn from_env() -> Result<Self> {
+        let template_dir = std::env::var("TEMPLATE_DIR").ok().map(PathBuf::from);
+        if let Some(dir) = &template_dir {
+            if !dir.is_dir() {
+                bail!("TEMPLATE_DIR {} is not a directory", dir.display());
+            }
+            println!("Using templates from {}", dir.display());
+        }
+
+        let site_url = match std::env::var("SITE_URL").ok().filter(|url| !url.trim().is_empty()) {
+            Some(url) => match safe_url(&url) {
+                Some(url) => Some(url),
+                None => bail!("SITE_URL {} is not an absolute http(s) URL", url),
+            },
+            None => None,
+        };
+
+        Ok(Self::new(template_dir).with_feeds(FeedFormat::from_env(), site_url))
+    }
+
+    pub fn new(template_dir: Option<PathBuf>) -> Self {
+        let mut env = Environment::new();
+        env.set_trim_blocks(true);
+        env.set_lstrip_blocks(true);
+        env.set_keep_trailing_newline(true);
+        // Site data comes from a third-party repository: escape it in every template,
+        // whatever the file extension of a custom template
+        env.set_auto_escape_callback(|_| AutoEscape::Html);
+        env.add_filter("safe_url", |url: String| safe_url(&url).unwrap_or_default());
+        env.set_loader(move |name| load_template(template_dir.as_deref(), name));
+        Renderer { env, feeds: vec![FeedFormat::Atom], site_url: None }
+    }
+
+    /// Publish `feeds`. RSS 2.0 requires absolute links, so it is skipped without `site_url`.
+    pub fn with_feeds(mut self, mut feeds: Vec<FeedFormat>, site_url: Option<String>) -> Self {
+        if site_url.is_none() && feeds.contains(&FeedFormat::Rss) {
+            println!("⚠ Warning: Skipping the RSS feed, which needs SITE_URL for its links");
+            feeds.retain(|f| *f != FeedFormat::Rss);
+        }
+        self.feeds = feeds;
+        self.site_url = site_url.map(|url| url.trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Render the static site: the index plus one page per service, keyed by path
+    /// relative to the output directory
+    pub fn render_site(
+        &self,
+        results: &[CheckResult],
+        history: &HistoryDocument,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<(PathBuf, String)>> {
+        let pages = page_names(results);
+        let mut files = vec![(PathBuf::from("index.html"), self.render_index(results, &pages, history, now)?)];
+
+        for (result, page) in results.iter().zip(&pages) {
+            let feeds = self.feed_links("../");
+            let context = service_view(result, page, history.for_site(&result.site.name), feeds, now);
+            let html = self.env.get_template("service.html")
+                .and_then(|template| template.render(&context))
+                .with_context(|| format!("Failed to render the page for {}", result.site.name))?;
+            files.push((Path::new(SERVICES_DIR).join(page), html));
+        }
+
+        if !self.feeds.is_empty() {
+            let context = self.feed_view(results, &pages, history, now);
+            for format in &self.feeds {
+                let xml = self.env.get_template(format.file_name())
+                    .and_then(|template| template.render(&context))
+                    .with_context(|| format!("Failed to render {}", format.file_name()))?;
+                files.push((PathBuf::from(format.file_name()), xml));
+            }
+        }
+
+        Ok(files)
+    }
+
+    fn render_index(
+        &self,
+        results: &[CheckResult],
+        pages: &[String],
+        history: &HistoryDocument,
+        now: DateTime<Utc>,
+    ) -> Result<String> {
+        let context = PageView {
+            generated_at: now.format(DATE_FORMAT).to_string(),
+            feeds: self.feed_links(""),
+            categories: group_by_category(results, pages, history, now),
+        };
+
+        self.env.get_template("index.html")
+            .and_then(|template| template.render(&context))
+            .context("Failed to render index.html")
+    }
+
+    /// `<link rel="alternate">` targets for a page `root` levels below the site root
+    fn feed_links(&self, root: &str) -> Vec<FeedLinkView> {
+        self.feeds.iter()
+            .map(|format| FeedLinkView {
+                title: format.title(),
+                mime_type: format.mime_type(),
+                href: format!("{}{}", root, format.file_name()),
+            })
+            .collect()
+    }
+
+    /// Link to `path` (relative to the site root) as seen from a feed reader
+    fn feed_link(&self, path: &str) -> String {
+        match &self.site_url {
+            Some(url) => format!("{}/{}", url, path),
+            None => path.to_string(),
+        }
+    }
+
+    /// Status changes across all services, newest first
+    fn feed_view(
+        &self,
+        results: &[CheckResult],
+        pages: &[String],
+        history: &HistoryDocument,
+        now: DateTime<Utc>,
+    ) -> FeedView {
+        let mut entries: Vec<(DateTime<Utc>, FeedEntryView)> = Vec::new();
+
+        for (result, page) in results.iter().zip(pages) {
+            let title = &result.site.title;
+            let records = history.for_site(&result.site.name);
+            let incidents = history::incidents(records);
+            let stem = page.trim_end_matches(".html");
+
+            for transition in history::transitions(records) {
+                let at = transition.at;
+                let (headline, summary) = match incidents.iter().find(|i| i.ended_at == Some(at)) {
+                    Some(incident) if transition.to == "online" => (
+                        format!("{} is back online", title),
+                        format!(
+                            "{} was {} for {} ({} checks) and is back online since {}.",
+                            title,
+                            describe_status(&incident.status),
+                            format_duration(at - incident.started_at),
+                            incident.checks,
+                            at.format(DATE_FORMAT),
+                        ),
+                    ),
+                    _ => (
+                        format!("{} is {}", title, describe_status(&transition.to)),
+                        format!(
+                            "{} went from {} to {} at {}.",
+                            title,
+                            describe_status(&transition.from),
+                            describe_status(&transition.to),
+                            at.format(DATE_FORMAT),
+                        ),
+                    ),
+                };
+
+                entries.push((at, FeedEntryView {
+                    id: format!("urn:onion-monitor:{}:{}", stem, at.timestamp()),
+                    title: headline,
+                    summary,
+                    link: self.feed_link(&format!("{}/{}", SERVICES_DIR, page)),
+                    updated: at.to_rfc3339(),
+                    updated_rfc2822: at.to_rfc2822(),
+                }));
+            }
+
+            for (at, change) in history::content_changes(records) {
+                entries.push((at, FeedEntryView {
+                    id: format!("urn:onion-monitor:{}:content:{}", stem, at.timestamp()),
+                    title: format!("{} changed its content", title),
+                    summary: format!("{} at {}.", change, at.format(DATE_FORMAT)),
+                    link: self.feed_link(&format!("{}/{}", SERVICES_DIR, page)),
+                    updated: at.to_rfc3339(),
+                    updated_rfc2822: at.to_rfc2822(),
+                }));
+            }
+        }
+
+        entries.sort_by_key(|(at, _)| std::cmp::Reverse(*at));
+        entries.truncate(MAX_FEED_ENTRIES);
+        let updated = entries.first().map(|(at, _)| *at).unwrap_or(now);
+
+        FeedView {
+            home: self.feed_link("index.html"),
+            // Atom readers reject a relative self link, so there is none without `site_url`
+            self_link: self.site_url.as_ref().map(|_| self.feed_link(FeedFormat::Atom.file_name())),
+            updated: updated.to_rfc3339(),
+            updated_rfc2822: updated.to_rfc2822(),
+            entries: entries.into_iter().map(|(_, entry)| entry).collect(),
+        }
+    }
+}
+
+/// Write the rendered site under `dir`, removing pages of services no longer listed and
+/// feeds no longer published
+pub fn write_site(dir: &Path, files: &[(PathBuf, String)]) -> Result<()> {
+    let services_dir = dir.join(SERVICES_DIR);
+    fs::create_dir_all(&services_dir)
+        .with_context(|| format!("Failed to create {}", services_dir.display()))?;
+
+    for (path, html) in files {
+        let path = dir.join(path);
+        store::write_atomic(&path, html.as_bytes())
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+
+    let current: HashSet<PathBuf> = files.iter().map(|(path, _)| dir.join(path)).collect();
+    for entry in fs::read_dir(&services_dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "html") && !current.contains(&path) {
+            fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove stale page {}", path.display()))?;
+        }
+    }
+    for format in FeedFormat::ALL {
+        let path = dir.join(format.file_name());
+        if !current.contains(&path) && path.exists() {
+            fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove stale feed {}", path.display()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// File name of each service's page; names are already slugs, but older data may not be
+fn page_names(results: &[CheckResult]) -> Vec<String> {
+    // Suffixes go by name and address rather than by position, so reordered sites keep their pages
+    let slugs: Vec<String> = results.iter().map(|result| slug::slugify(&result.site.name)).collect();
+    let mut order: Vec<usize> = (0..results.len()).collect();
+    order.sort_by_key(|&i| (&slugs[i], &results[i].site.name, &results[i].site.onion_address));
+
+    let mut taken = HashSet::new();
+    let mut names = vec![String::new(); results.len()];
+    for i in order {
+        let name = slug::disambiguate(&slugs[i], &taken);
+        taken.insert(name.clone());
+        names[i] = format!("{}.html", name);
+    }
+    names
+}
+
+fn load_template(dir: Option<&Path>, name: &str) -> Result<Option<String>, minijinja::Error> {
+    if let Some(dir) = dir {
+        // Template names come from other templates; keep them inside the directory
+        let relative = Path::new(name);
+        if relative.components().all(|c| matches!(c, Component::Normal(_))) {
+            let path = dir.join(relative);
+            if path.is_file() {
+                return std::fs::read_to_string(&path).map(Some).map_err(|e| {
+                    minijinja::Error::new(minijinja::ErrorKind::InvalidOperation, "failed to read template")
+                        .with_source(e)
+                });
+            }
+        }
+    }
+
+    Ok(EMBEDDED_TEMPLATES.iter()
+        .find(|(embedded, _)| *embedded == name)
+        .map(|(_, source)| source.to_string()))
+}
+
+#[derive(Serialize)]
+struct PageView {
+    generated_at: String,
+    feeds: Vec<FeedLinkView>,
+    categories: Vec<CategoryView>,
+}
+
+#[derive(Serialize)]
+struct CategoryView {
+    name: String,
+    sites: Vec<SiteView>,
+}
+
+#[derive(Serialize)]
+struct SiteView {
+    /// The raw site, for custom templates that need more than the prepared fields
+    raw: OnionSite,
+    /// Link to the service's page, relative to the index
+    page: String,
+    title: String,
+    name: String,
+    onion_address: String,
+    status: String,
+    status_class: &'static str,
+    status_text: &'static str,
+    response_time: String,
+    last_checked: String,
+    description: Option<String>,
+    tags: Vec<String>,
+    links: Vec<LinkView>,
+    badges: Vec<BadgeView>,
+    /// Why the descriptor check flags the service as at risk, across all endpoints
+    risks: Vec<String>,
+    /// Certificate and redirect problems across all endpoints
+    warnings: Vec<String>,
+    /// Vantage points that could not reach every endpoint, when others could
+    unreachable_from: Vec<String>,
+    endpoints: Vec<EndpointView>,
+    /// Availability over the uptime bar's window
+    uptime: String,
+    /// Inline SVG, marked safe so it is not escaped
+    uptime_bar: Value,
+    /// Inline SVG, marked safe so it is not escaped
+    sparkline: Option<Value>,
+}
+
+#[derive(Serialize)]
+struct LinkView {
+    label: &'static str,
+    url: String,
+}
+
+#[derive(Serialize)]
+struct BadgeView {
+    class: &'static str,
+    text: &'static str,
+    checked_at: String,
+}
+
+#[derive(Serialize)]
+struct EndpointView {
+    url: String,
+    label: Option<String>,
+    status: String,
+    status_class: &'static str,
+    status_text: &'static str,
+    response_time: String,
+    last_checked: String,
+    /// Summary of the control port diagnosis of the last failure
+    diagnosis: Option<String>,
+    descriptor: Option<DescriptorView>,
+    timings: Option<TimingsView>,
+    certificate: Option<CertificateView>,
+    redirects: Option<RedirectCheck>,
+    content: Option<ContentFingerprint>,
+    vantages: Vec<VantageView>,
+}
+
+#[derive(Serialize)]
+struct CertificateView {
+    subject: String,
+    names: String,
+    issuer: String,
+    valid_from: String,
+    expires: String,
+    /// Self-signed, CA-issued, or the reason it did not verify
+    trust: String,
+    covers_host: bool,
+    warnings: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct TimingsView {
+    socks_handshake: String,
+    circuit: String,
+    tls: String,
+    ttfb: String,
+    total: String,
+}
+
+#[derive(Serialize)]
+struct VantageView {
+    name: String,
+    status_class: &'static str,
+    status_text: &'static str,
+    response_time: String,
+}
+
+#[derive(Serialize)]
+struct DescriptorView {
+    found: bool,
+    revision: String,
+    intro_points: String,
+    published_at: String,
+    lifetime: String,
+    signing_key_expires: String,
+    at_risk: bool,
+    risks: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct ServicePageView {
+    generated_at: String,
+    feeds: Vec<FeedLinkView>,
+    site: SiteView,
+    uptime: Vec<UptimeView>,
+    /// Inline SVG, marked safe so it is not escaped
+    latency_chart: Option<Value>,
+    /// Newest first
+    incidents: Vec<IncidentView>,
+    /// Newest first, at most `MAX_HISTORY_ROWS`
+    checks: Vec<CheckView>,
+    checks_omitted: usize,
+}
+
+#[derive(Serialize)]
+struct FeedLinkView {
+    title: &'static str,
+    mime_type: &'static str,
+    href: String,
+}
+
+#[derive(Serialize)]
+struct FeedView {
+    home: String,
+    self_link: Option<String>,
+    updated: String,
+    updated_rfc2822: String,
+    entries: Vec<FeedEntryView>,
+}
+
+#[derive(Serialize)]
+struct FeedEntryView {
+    id: String,
+    title: String,
+    summary: String,
+    link: String,
+    updated: String,
+    updated_rfc2822: String,
+}
+
+#[derive(Serialize)]
+struct UptimeView {
+    label: &'static str,
+    value: String,
+}
+
+#[derive(Serialize)]
+struct IncidentView {
+    started_at: String,
+    ended_at: Option<String>,
+    duration: String,
+    status_class: &'static str,
+    status_text: &'static str,
+    checks: usize,
+}
+
+#[derive(Serialize)]
+struct CheckView {
+    checked_at: String,
+    status_class: &'static str,
+    status_text: &'static str,
+    response_time: String,
+    /// Time Tor took to set up the circuit, as opposed to the server's own latency
+    circuit: String,
+    ttfb: String,
+    content_change: Option<String>,
+}
+
+pub fn status_class(status: &str) -> &'static str {
+    match status {
+        "online" => "status-online",
+        "partial" => "status-partial",
+        "offline" => "status-offline",
+        "unknown" => "status-unknown",
+        _ => "status-error",
+    }
+}
+
+pub fn status_text(status: &str) -> &'static str {
+    match status {
+        "online" => "Online",
+        "partial" => "Partial",
+        "offline" => "Offline",
+        "unknown" => "Unknown",
+        _ => "Error",
+    }
+}
+
+/// Status as it reads in a sentence
+fn describe_status(status: &str) -> String {
+    match status {
+        "online" => "online".to_string(),
+        "partial" => "partially online".to_string(),
+        "offline" => "offline".to_string(),
+        other => format!("returning {}", other),
+    }
+}
+
+/// Return `url` normalized if it is an absolute http(s) URL, so it is safe as a link target.
+///
+/// Escaping alone does not stop `javascript:` or `data:` URLs from running in an `href`.
+pub fn safe_url(url: &str) -> Option<String> {
+    let parsed = Url::parse(url.trim()).ok()?;
+    match parsed.scheme() {
+        "http" | "https" if parsed.host_str().is_some() => Some(parsed.to_string()),
+        _ => None,
+    }
+}
+
+fn format_response_time(ms: Option<u64>) -> String {
+    ms.map(|ms| format!("{}ms", ms)).unwrap_or_else(|| "N/A".to_string())
+}
+
+fn format_uptime(pct: Option<f64>) -> String {
+    pct.map(|pct| format!("{:.2}%", pct)).unwrap_or_else(|| "N/A".to_string())
+}
+
+fn format_checked(at: Option<DateTime<Utc>>) -> String {
+    at.map(|dt| dt.format(DATE_FORMAT).to_string())
+        .unwrap_or_else(|| "Never".to_string())
+}
+
+fn format_duration(duration: Duration) -> String {
+    let minutes = duration.num_minutes().max(0);
+    match (minutes / (24 * 60), minutes / 60 % 24, minutes % 60) {
+        (0, 0, m) => format!("{}m", m),
+        (0, h, m) => format!("{}h {}m", h, m),
+        (d, h, _) => format!("{}d {}h", d, h),
+    }
+}
+
+/// Group sites by category, alphabetically, with uncategorized services last
+fn group_by_category(
+    results: &[CheckResult],
+    pages: &[String],
+    history: &HistoryDocument,
+    now: DateTime<Utc>,
+) -> Vec<CategoryView> {
+    let mut categories: BTreeMap<(bool, String), Vec<SiteView>> = BTreeMap::new();
+
+    for (result, page) in results.iter().zip(pages) {
+        let key = match result.site.metadata.category.as_deref().map(str::trim) {
+            Some(category) if !category.is_empty() => (false, category.to_string()),
+            _ => (true, "Other".to_string()),
+        };
+        let page = format!("{}/{}", SERVICES_DIR, page);
+        let view = site_view(result, page, history.for_site(&result.site.name), now);
+        categories.entry(key).or_default().push(view);
+    }
+
+    categories.into_iter()
+        .map(|((_, name), sites)| CategoryView { name, sites })
+        .collect()
+}
+
+fn site_view(result: &CheckResult, page: String, records: &[CheckRecord], now: DateTime<Utc>) -> SiteView {
+    let site = &result.site;
+    let metadata = &site.metadata;
+
+    let links = [("Website", &metadata.official_website), ("GitHub", &metadata.github)]
+        .into_iter()
+        .filter_map(|(label, url)| {
+            let url = safe_url(url.as_deref()?)?;
+            Some(LinkView { label, url })
+        })
+        .collect();
+
+    SiteView {
+        raw: site.clone(),
+        page,
+        title: site.title.clone(),
+        name: site.name.clone(),
+        onion_address: site.onion_address.clone(),
+        status: site.status.clone(),
+        status_class: status_class(&site.status),
+        status_text: status_text(&site.status),
+        response_time: format_response_time(result.response_time_ms),
+        last_checked: format_checked(site.last_checked),
+        description: metadata.description.clone(),
+        tags: metadata.tags.clone(),
+        links,
+        badges: verification_badges(site),
+        risks: descriptor_risks(site),
+        warnings: endpoint_warnings(site),
+        unreachable_from: unreachable_from(site),
+        endpoints: site.endpoints.iter().map(endpoint_view).collect(),
+        uptime: format_uptime(history::uptime(records, now - Duration::days(UPTIME_BAR_DAYS.into()))),
+        uptime_bar: Value::from_safe_string(svg::uptime_bar(records, now, UPTIME_BAR_DAYS, 270, 24)),
+        sparkline: svg::sparkline(records, SPARKLINE_CHECKS, 120, 24).map(Value::from_safe_string),
+    }
+}
+
+fn endpoint_view(endpoint: &Endpoint) -> EndpointView {
+    EndpointView {
+        url: endpoint.url.clone(),
+        label: endpoint.label.clone(),
+        status: endpoint.status.clone(),
+        status_class: status_class(&endpoint.status),
+        status_text: status_text(&endpoint.status),
+        response_time: format_response_time(endpoint.response_time_ms),
+        last_checked: format_checked(endpoint.last_checked),
+        diagnosis: endpoint.diagnosis.as_ref().map(diagnosis_summary),
+        descriptor: endpoint.descriptor.as_ref().map(descriptor_view),
+        timings: endpoint.timings.as_ref().map(timings_view),
+        certificate: endpoint.certificate.as_ref().map(certificate_view),
+        redirects: endpoint.redirects.clone(),
+        content: endpoint.content.clone(),
+        vantages: endpoint.vantages.iter()
+            .map(|result| VantageView {
+                name: result.vantage.clone(),
+                status_class: status_class(&result.status),
+                status_text: status_text(&result.status),
+                response_time: format_response_time(result.response_time_ms),
+            })
+            .collect(),
+    }
+}
+
+fn timings_view(timings: &Timings) -> TimingsView {
+    let phase = |ms: Option<u64>| ms.map(|ms| format!("{}ms", ms)).unwrap_or_else(|| "-".to_string());
+    TimingsView {
+        socks_handshake: phase(timings.socks_handshake_ms),
+        circuit: phase(timings.circuit_ms),
+        tls: phase(timings.tls_ms),
+        ttfb: phase(timings.ttfb_ms),
+        total: phase(Some(timings.total_ms)),
+    }
+}
+
+/// Vantage points that failed to reach an endpoint some other vantage point reached
+fn unreachable_from(site: &OnionSite) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for endpoint in site.endpoints.iter().filter(|e| e.status == "partial") {
+        for result in endpoint.vantages.iter().filter(|r| r.status != "online") {
+            if !names.contains(&result.vantage) {
+                names.push(result.vantage.clone());
+            }
+        }
+    }
+    names
+}
+
+fn descriptor_view(check: &DescriptorCheck) -> DescriptorView {
+    DescriptorView {
+        found: check.found,
+        revision: check.revision_counter.map(|r| r.to_string()).unwrap_or_else(|| "-".to_string()),
+        intro_points: match (check.found, check.intro_points) {
+            (false, _) => "-".to_string(),
+            (true, Some(count)) => count.to_string(),
+            (true, None) => "Hidden (client authorization)".to_string(),
+        },
+        published_at: format_checked(check.published_at),
+        lifetime: check.lifetime_minutes
+            .map(|m| format_duration(Duration::minutes(m.into())))
+            .unwrap_or_else(|| "-".to_string()),
+        signing_key_expires: format_checked(check.signing_key_expires),
+        at_risk: check.at_risk,
+        risks: check.risks.clone(),
+    }
+}
+
+fn certificate_view(check: &CertificateCheck) -> CertificateView {
+    CertificateView {
+        subject: check.subject.clone(),
+        names: if check.sans.is_empty() { "-".to_string() } else { check.sans.join(", ") },
+        issuer: check.issuer.clone(),
+        valid_from: format_checked(check.not_before),
+        expires: format_checked(check.not_after),
+        trust: match (check.trusted, check.self_signed, &check.verify_error) {
+            (true, _, _) => "Trusted CA".to_string(),
+            (false, true, _) => "Self-signed".to_string(),
+            (false, false, error) => format!("Untrusted ({})", error.as_deref().unwrap_or("unknown")),
+        },
+        covers_host: check.covers_host,
+        warnings: check.warnings.clone(),
+    }
+}
+
+/// Distinct certificate, redirect and content warnings of a site's endpoints, in endpoint order
+fn endpoint_warnings(site: &OnionSite) -> Vec<String> {
+    let mut seen = HashSet::new();
+    site.endpoints.iter()
+        .flat_map(|e| {
+            let mut warnings: Vec<String> = Vec::new();
+            warnings.extend(e.certificate.iter().flat_map(|check| check.warnings.iter().cloned()));
+            warnings.extend(e.redirects.iter().flat_map(|check| check.warnings.iter().cloned()));
+            if let Some(content) = &e.content {
+                warnings.extend(content.placeholder.as_ref().map(|p| format!("Landing page looks like a {}", p)));
+                warnings.extend(content.change.clone());
+            }
+            warnings
+        })
+        .filter(|warning| seen.insert(warning.clone()))
+        .collect()
+}
+
+/// Distinct descriptor risks of a site's endpoints, in endpoint order
+fn descriptor_risks(site: &OnionSite) -> Vec<String> {
+    let mut seen = HashSet::new();
+    site.endpoints.iter()
+        .filter_map(|e| e.descriptor.as_ref())
+        .flat_map(|check| check.risks.iter())
+        .filter(|risk| seen.insert(risk.as_str()))
+        .cloned()
+        .collect()
+}
+
+fn diagnosis_summary(diagnosis: &Diagnosis) -> String {
+    let mut parts = vec![format!("Failed at {}", diagnosis.failed_at.label())];
+
+    if diagnosis.descriptor_found {
+        let intro_points = diagnosis.intro_points
+            .map(|n| format!("{} introduction points", n))
+            .unwrap_or_else(|| "introduction points unknown".to_string());
+        parts.push(format!("descriptor found ({})", intro_points));
+    } else if diagnosis.hsdirs_queried > 0 {
+        parts.push(format!("descriptor not found on {} HSDirs", diagnosis.hsdirs_queried));
+    }
+
+    let circuits = &diagnosis.circuits;
+    if circuits.intro_failed > 0 {
+        parts.push(format!("{} introduction circuits failed", circuits.intro_failed));
+    }
+    if circuits.rend_failed > 0 {
+        parts.push(format!("{} rendezvous circuits failed", circuits.rend_failed));
+    }
+    if let Some(details) = &diagnosis.details {
+        parts.push(details.clone());
+    }
+
+    parts.join("; ")
+}
+
+fn service_view(
+    result: &CheckResult,
+    page: &str,
+    records: &[CheckRecord],
+    feeds: Vec<FeedLinkView>,
+    now: DateTime<Utc>,
+) -> ServicePageView {
+    let uptime = [("24 hours", 1), ("7 days", 7), ("30 days", 30), ("90 days", 90)]
+        .into_iter()
+        .map(|(label, days)| UptimeView {
+            label,
+            value: format_uptime(history::uptime(records, now - Duration::days(days))),
+        })
+        .collect();
+
+    let incidents = history::incidents(records).iter().rev().map(|i| incident_view(i, now)).collect();
+
+    let checks = records.iter()
+        .rev()
+        .take(MAX_HISTORY_ROWS)
+        .map(|record| CheckView {
+            checked_at: record.checked_at.format(DATE_FORMAT).to_string(),
+            status_class: status_class(&record.status),
+            status_text: status_text(&record.status),
+            response_time: format_response_time(record.response_time_ms),
+            circuit: format_response_time(record.timings.as_ref().and_then(|t| t.circuit_ms)),
+            ttfb: format_response_time(record.timings.as_ref().and_then(|t| t.ttfb_ms)),
+            content_change: record.content_change.clone(),
+        })
+        .collect();
+
+    ServicePageView {
+        generated_at: now.format(DATE_FORMAT).to_string(),
+        feeds,
+        site: site_view(result, page.to_string(), records, now),
+        uptime,
+        latency_chart: svg::latency_chart(records, CHART_WIDTH, CHART_HEIGHT).map(Value::from_safe_string),
+        incidents,
+        checks,
+        checks_omitted: records.len().saturating_sub(MAX_HISTORY_ROWS),
+    }
+}
+
+fn incident_view(incident: &Incident, now: DateTime<Utc>) -> IncidentView {
+    IncidentView {
+        started_at: incident.started_at.format(DATE_FORMAT).to_string(),
+        ended_at: incident.ended_at.map(|dt| dt.format(DATE_FORMAT).to_string()),
+        duration: format_duration(incident.ended_at.unwrap_or(now) - incident.started_at),
+        status_class: status_class(&incident.status),
+        status_text: status_text(&incident.status),
+        checks: incident.checks,
+    }
+}
+
+fn verification_badges(site: &OnionSite) -> Vec<BadgeView> {
+    let mut badges = Vec::new();
+
+    if let Some(check) = &site.onion_location {
+        let (class, text) = match check.status {
+            VerificationStatus::Verified => ("verification-verified", "✓ Onion-Location verified"),
+            VerificationStatus::Mismatch => ("verification-mismatch", "⚠ Onion-Location mismatch"),
+            VerificationStatus::Unverified | VerificationStatus::Claimed => ("verification-unverified", "Unverified"),
+            VerificationStatus::Error => ("verification-unverified", "Unverified (website unreachable)"),
+        };
+        badges.push(BadgeView { class, text, checked_at: check.checked_at.format(DATE_FORMAT).to_string() });
+    }
+
+    if let Some(check) = &site.ownership {
+        let (class, text) = match (check.status, check.method) {
+            (VerificationStatus::Verified, Some(ProofMethod::WellKnown)) => ("verification-verified", "🔒 Owner verified (.well-known)"),
+            (VerificationStatus::Verified, Some(ProofMethod::Dns)) => ("verification-verified", "🔒 Owner verified (DNS)"),
+            // Backlinks recorded as verified by older versions are only claims as well
+            (VerificationStatus::Claimed, _) | (VerificationStatus::Verified, Some(ProofMethod::OnionBacklink)) => {
+                ("verification-unverified", "Ownership claimed by the onion only")
+            }
+            (VerificationStatus::Mismatch, _) => ("verification-mismatch", "⚠ Ownership proof mismatch"),
+            _ => ("verification-unverified", "No ownership proof"),
+        };
+        badges.push(BadgeView { class, text, checked_at: check.checked_at.format(DATE_FORMAT).to_string() });
+    }
+
+    badges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const HOSTILE: &str = "<script>alert(1)</script>\"'><img src=x onerror=alert(1)>";
+
+    fn result(site: serde_json::Value) -> CheckResult {
+        let mut site: OnionSite = serde_json::from_value(site).unwrap();
+        site.ensure_endpoints();
+        CheckResult { site, response_time_ms: None }
+    }
+
+    fn render_index(renderer: &Renderer, results: &[CheckResult]) -> Result<String> {
+        let files = renderer.render_site(results, &HistoryDocument::default(), Utc::now())?;
+        Ok(files.into_iter().next().expect("index is always rendered").1)
+    }
+
+    fn hostile_site() -> serde_json::Value {
+        json!({
+            "title": HOSTILE,
+            "name": HOSTILE,
+            "onion_address": format!("http://abc.onion/{}", HOSTILE),
+            "status": HOSTILE,
+            "prev_status": "unknown",
+            "last_checked": null,
+            "category": HOSTILE,
+            "description": HOSTILE,
+            "official_website": "javascript:alert(1)",
+            "github": "https://github.com/example/\"onmouseover=\"alert(1)",
+            "tags": [HOSTILE],
+            "endpoints": [
+                { "url": HOSTILE, "label": HOSTILE, "status": "online", "prev_status": "unknown", "last_checked": null },
+                { "url": "http://def.onion", "status": "offline", "prev_status": "unknown", "last_checked": null }
+            ]
+        })
+    }
+
+    /// A site with a single endpoint; `endpoint_extra` adds to or overrides the endpoint fields
+    fn example_site(endpoint_extra: serde_json::Value) -> serde_json::Value {
+        let mut endpoint = json!({ "url": "http://abc.onion", "status": "online", "prev_status": "online", "last_checked": null });
+        endpoint.as_object_mut().unwrap().extend(endpoint_extra.as_object().unwrap().clone());
+        json!({
+            "title": "Example",
+            "name": "example",
+            "onion_address": endpoint["url"],
+            "status": endpoint["status"],
+            "prev_status": "online",
+            "last_checked": null,
+            "endpoints": [endpoint]
+        })
+    }
+
+    fn record(status: &str, checked_at: DateTime<Utc>) -> CheckRecord {
+        CheckRecord {
+            checked_at,
+            status: status.into(),
+            response_time_ms: Some(1000).filter(|_| status == "online"),
+            timings: None,
+            content_change: None,
+        }
+    }
+
+    #[test]
+    fn escapes_untrusted_site_fields() {
+        let html = render_index(&Renderer::new(None), &[result(hostile_site())]).unwrap();
+
+        assert!(!html.contains("<script>alert"));
+        assert!(!html.contains("<img src=x"));
+        assert!(!html.contains("\"'><"));
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;&#x2f;script&gt;"));
+    }
+
+    #[test]
+    fn drops_non_http_links() {
+        let html = render_index(&Renderer::new(None), &[result(hostile_site())]).unwrap();
+
+        assert!(!html.contains("javascript:"));
+        assert!(!html.contains("\"onmouseover"));
+        assert!(html.contains(">GitHub</a>"));
+        assert!(!html.contains(">Website</a>"));
+    }
+
+    #[test]
+    fn escapes_custom_templates_regardless_of_extension() {
+        let dir = std::env::temp_dir().join(format!("onion-monitor-templates-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("index.html"),
+            "{% for c in categories %}{% for site in c.sites %}{% include \"item.txt\" %}{% endfor %}{% endfor %}",
+        ).unwrap();
+        std::fs::write(dir.join("item.txt"), "<a href=\"{{ site.raw.official_website | safe_url }}\">{{ site.title }}</a>").unwrap();
+
+        let html = render_index(&Renderer::new(Some(dir.clone())), &[result(hostile_site())]);
+        std::fs::remove_dir_all(&dir).unwrap();
+        let html = html.unwrap();
+
+        assert_eq!(html, "<a href=\"\">&lt;script&gt;alert(1)&lt;&#x2f;script&gt;&quot;&#x27;&gt;&lt;img src=x onerror=alert(1)&gt;</a>");
+    }
+
+    #[test]
+    fn safe_url_accepts_only_absolute_http_urls() {
+        assert_eq!(safe_url("https://example.org/a b").as_deref(), Some("https://example.org/a%20b"));
+        assert_eq!(safe_url(" http://abc.onion ").as_deref(), Some("http://abc.onion/"));
+        assert_eq!(safe_url("javascript:alert(1)"), None);
+        assert_eq!(safe_url("JavaScript:alert(1)"), None);
+        assert_eq!(safe_url("data:text/html,<script>alert(1)</script>"), None);
+        assert_eq!(safe_url("//example.org"), None);
+        assert_eq!(safe_url("/relative"), None);
+    }
+
+    #[test]
+    fn keeps_page_names_when_sites_are_reordered() {
+        let site = |name: &str, address: &str| {
+            let mut site = hostile_site();
+            site["name"] = json!(name);
+            site["onion_address"] = json!(address);
+            result(site)
+        };
+        let sites = [site("Wallet", "http://b.onion"), site("wallet", "http://a.onion"), site("bridge", "http://c.onion")];
+        assert_eq!(page_names(&sites), ["wallet.html", "wallet-2.html", "bridge.html"]);
+
+        let [a, b, c] = sites;
+        assert_eq!(page_names(&[c, b, a]), ["bridge.html", "wallet-2.html", "wallet.html"]);
+    }
+
+    #[test]
+    fn renders_a_page_per_service_linked_from_the_index() {
+        let now = Utc::now();
+        let mut history = HistoryDocument::default();
+        history.sites.insert(HOSTILE.to_string(), vec![
+            record("online", now - Duration::hours(2)),
+            record("offline", now - Duration::hours(1)),
+            record("online", now),
+        ]);
+        let mut other = hostile_site();
+        other["name"] = json!("<script>");
+
+        let files = Renderer::new(None)
+            .render_site(&[result(hostile_site()), result(other)], &history, now)
+            .unwrap();
+        let paths: Vec<_> = files.iter().map(|(path, _)| path.to_str().unwrap()).collect();
+        assert_eq!(paths, ["index.html", "services/script-alert-1-script-img-src-x-onerror-alert-1.html", "services/script.html", "atom.xml"]);
+
+        let index = &files[0].1;
+        assert!(index.contains("href=\"services&#x2f;script.html\""));
+
+        let page = &files[1].1;
+        assert!(!page.contains("<script>alert"));
+        assert!(page.contains("<svg class=\"latency-chart\""));
+        assert!(page.contains("<circle cx="));
+        assert!(page.contains("stroke=\"#dc2626\""));
+        assert!(page.contains("1h 0m"));
+        assert!(page.contains("66.67%"));
+    }
+
+    #[test]
+    fn flags_services_whose_descriptor_is_at_risk() {
+        let site = example_site(json!({
+            "descriptor": {
+                "found": true, "revision_counter": 42, "lifetime_minutes": 180,
+                "published_at": "2026-01-01T10:00:00Z", "signing_key_expires": "2026-01-02T12:00:00Z",
+                "intro_points": 1, "at_risk": true, "risks": ["Only 1 introduction point listed"],
+                "checked_at": "2026-01-01T11:00:00Z"
+            }
+        }));
+
+        let files = Renderer::new(None).render_site(&[result(site)], &HistoryDocument::default(), Utc::now()).unwrap();
+        assert!(files[0].1.contains("⚠ At risk: Only 1 introduction point listed"));
+
+        let page = &files[1].1;
+        assert!(page.contains("Onion Descriptor"));
+        assert!(page.contains("<td>42</td>"));
+        assert!(page.contains("<td>3h 0m</td>"));
+        assert!(page.contains("Jan 01, 2026 10:00 UTC"));
+    }
+
+    #[test]
+    fn shows_results_per_vantage_point() {
+        let site = example_site(json!({
+            "status": "partial",
+            "vantages": [
+                { "vantage": "eu", "status": "online", "response_time_ms": 900, "checked_at": "2026-01-01T10:00:00Z" },
+                { "vantage": "us", "status": "offline", "response_time_ms": null, "checked_at": "2026-01-01T10:01:00Z" }
+            ]
+        }));
+
+        let files = Renderer::new(None).render_site(&[result(site)], &HistoryDocument::default(), Utc::now()).unwrap();
+        assert!(files[0].1.contains("Unreachable from us only"));
+
+        let page = &files[1].1;
+        assert!(page.contains("</span>eu 900ms</span>"));
+        assert!(page.contains("</span>us N&#x2f;A</span>"));
+    }
+
+    #[test]
+    fn warns_about_certificate_problems() {
+        let site = example_site(json!({
+            "url": "https://abc.onion",
+            "certificate": {
+                "subject": "CN = abc.onion", "sans": ["abc.onion"], "issuer": "CN = HARICA DV",
+                "not_before": "2026-01-01T00:00:00Z", "not_after": "2026-01-10T00:00:00Z",
+                "self_signed": false, "covers_host": true, "trusted": true,
+                "warnings": ["Certificate expires on 2026-01-10 (in 5 days)"],
+                "checked_at": "2026-01-05T00:00:00Z"
+            }
+        }));
+
+        let files = Renderer::new(None).render_site(&[result(site)], &HistoryDocument::default(), Utc::now()).unwrap();
+        assert!(files[0].1.contains("⚠ Certificate expires on 2026-01-10 (in 5 days)"));
+
+        let page = &files[1].1;
+        assert!(page.contains("TLS Certificate"));
+        assert!(page.contains("<td>CN = HARICA DV</td>"));
+        assert!(page.contains("<td>Jan 10, 2026 00:00 UTC</td>"));
+        assert!(page.contains("<td>Trusted CA</td>"));
+    }
+
+    #[test]
+    fn shows_redirect_chains() {
+        let site = example_site(json!({
+            "redirects": {
+                "chain": [
+                    { "url": "http://abc.onion", "status_code": 301, "location": "https://abc.onion/" },
+                    { "url": "https://abc.onion/", "status_code": 302, "location": "https://example.com/" }
+                ],
+                "final_url": "https://example.com/", "leaves_onion": true, "looped": false,
+                "warnings": ["Redirects off the onion to https://example.com/"]
+            }
+        }));
+
+        let files = Renderer::new(None).render_site(&[result(site)], &HistoryDocument::default(), Utc::now()).unwrap();
+        assert!(files[0].1.contains("⚠ Redirects off the onion to https:&#x2f;&#x2f;example.com&#x2f;"));
+
+        let page = &files[1].1;
+        assert!(page.contains("<h3 class=\"category-title\">Redirects</h3>"));
+        assert!(page.contains("<td>302</td>"));
+        assert!(page.contains("<td class=\"endpoint-url\">https:&#x2f;&#x2f;example.com&#x2f;</td>"));
+    }
+
+    #[test]
+    fn breaks_response_times_into_phases() {
+        let now = Utc::now();
+        let timings = Timings { socks_handshake_ms: Some(3), circuit_ms: Some(2400), ttfb_ms: Some(350), total_ms: 2800, ..Timings::default() };
+        let mut history = HistoryDocument::default();
+        history.sites.insert("example".to_string(), vec![
+            CheckRecord { response_time_ms: Some(2800), timings: Some(timings.clone()), ..record("online", now) },
+        ]);
+        let site = example_site(json!({
+            "response_time_ms": 2800, "timings": timings
+        }));
+
+        let files = Renderer::new(None).render_site(&[result(site)], &history, now).unwrap();
+        let page = &files[1].1;
+        assert!(page.contains("Timing Breakdown"));
+        assert!(page.contains("<td>3ms</td>\n                <td>2400ms</td>\n                <td>-</td>\n                <td>350ms</td>"));
+        // The check history separates circuit setup from the server's latency
+        assert!(page.contains("<td>2800ms</td>\n                <td>2400ms</td>\n                <td>350ms</td>"));
+    }
+
+    #[test]
+    fn pages_are_self_contained() {
+        let now = Utc::now();
+        let mut history = HistoryDocument::default();
+        history.sites.insert("example".to_string(), vec![
+            record("online", now),
+        ]);
+        let site = json!({
+            "title": "Example",
+            "name": "example",
+            "onion_address": "http://abc.onion",
+            "status": "online",
+            "prev_status": "unknown",
+            "last_checked": now,
+            "official_website": "https://example.org",
+        });
+
+        let files = Renderer::new(None).render_site(&[result(site)], &history, now).unwrap();
+        for (path, html) in files.iter().filter(|(path, _)| path.extension().unwrap() == "html") {
+            let path = path.display();
+            assert!(html.contains("<meta http-equiv=\"Content-Security-Policy\" content=\"default-src 'none';"), "{}", path);
+            assert!(!html.contains("<script"), "{} runs JavaScript", path);
+            assert!(!html.contains("@import") && !html.contains("url("), "{} loads CSS resources", path);
+            assert!(!html.contains(" src="), "{} embeds a resource", path);
+            for link in html.split("<link").skip(1) {
+                let link = &link[..link.find('>').unwrap()];
+                assert!(link.contains("href=\"data:") || link.contains("rel=\"alternate\""), "{} links an external resource", path);
+            }
+        }
+    }
+
+    #[test]
+    fn cards_show_daily_uptime_and_latency() {
+        let now = Utc::now();
+        let mut history = HistoryDocument::default();
+        history.sites.insert("example".to_string(), vec![
+            record("offline", now - Duration::days(1)),
+            record("online", now - Duration::minutes(10)),
+            record("online", now),
+        ]);
+        let site = json!({
+            "title": "Example",
+            "name": "example",
+            "onion_address": "http://abc.onion",
+            "status": "online",
+            "prev_status": "online",
+            "last_checked": now,
+        });
+
+        let files = Renderer::new(None).render_site(&[result(site)], &history, now).unwrap();
+        let index = &files[0].1;
+
+        assert_eq!(index.matches("<rect ").count(), UPTIME_BAR_DAYS as usize);
+        assert_eq!(index.matches("fill=\"#cbd5e0\"").count(), UPTIME_BAR_DAYS as usize - 2);
+        assert!(index.contains("fill=\"#f56565\"><title>"));
+        assert!(index.contains("fill=\"#48bb78\"><title>"));
+        assert!(index.contains("66.67% uptime"));
+        assert!(index.contains("<svg class=\"sparkline\""));
+        assert!(index.contains("<polyline"));
+    }
+
+    #[test]
+    fn feeds_list_status_changes_newest_first() {
+        let now = Utc::now();
+        let started = now - Duration::hours(3);
+        let mut history = HistoryDocument::default();
+        history.sites.insert(HOSTILE.to_string(), vec![
+            record("online", now - Duration::hours(4)),
+            record("offline", started),
+            record("offline", now - Duration::hours(2)),
+            record("online", now),
+        ]);
+
+        let files = Renderer::new(None)
+            .with_feeds(vec![FeedFormat::Atom, FeedFormat::Rss], Some("http://monitor.onion/".to_string()))
+            .render_site(&[result(hostile_site())], &history, now)
+            .unwrap();
+        let feed = |name: &str| &files.iter().find(|(path, _)| path == Path::new(name)).unwrap().1;
+
+        let atom = feed("atom.xml");
+        assert!(atom.starts_with("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">"));
+        assert!(!atom.contains("<script>"));
+        assert_eq!(atom.matches("<entry>").count(), 2);
+        let recovered = atom.find("is back online</title>").unwrap();
+        let went_down = atom.find("is offline</title>").unwrap();
+        assert!(recovered < went_down);
+        assert!(atom.contains("was offline for 3h 0m (2 checks)"));
+        assert!(atom.contains(&format!("<updated>{}</updated>", now.to_rfc3339())));
+        assert!(atom.contains("href=\"http:&#x2f;&#x2f;monitor.onion&#x2f;services&#x2f;"));
+        assert!(atom.contains("<link rel=\"self\" type=\"application/atom+xml\" href=\"http:&#x2f;&#x2f;monitor.onion&#x2f;atom.xml\"/>"));
+
+        let rss = feed("rss.xml");
+        assert_eq!(rss.matches("<item>").count(), 2);
+        assert!(rss.contains(&format!("<pubDate>{}</pubDate>", started.to_rfc2822())));
+
+        let index = &files[0].1;
+        assert!(index.contains("<link rel=\"alternate\" type=\"application&#x2f;atom+xml\" title=\"Atom feed\" href=\"atom.xml\">"));
+
+        // RSS needs absolute links
+        let files = Renderer::new(None)
+            .with_feeds(vec![FeedFormat::Atom, FeedFormat::Rss], None)
+            .render_site(&[result(hostile_site())], &history, now)
+            .unwrap();
+        assert!(files.iter().any(|(path, _)| path == Path::new("atom.xml")));
+        assert!(!files.iter().any(|(path, _)| path == Path::new("rss.xml")));
+        assert!(!files[0].1.contains("rss.xml"));
+        // Atom readers reject a relative self link
+        let atom = &files.iter().find(|(path, _)| path == Path::new("atom.xml")).unwrap().1;
+        assert!(!atom.contains("rel=\"self\""));
+    }
+
+    #[test]
+    fn removes_feeds_that_are_no_longer_published() {
+        let dir = std::env::temp_dir().join(format!("onion-monitor-feeds-{}", std::process::id()));
+        let history = HistoryDocument::default();
+        let render = |feeds| {
+            Renderer::new(None)
+                .with_feeds(feeds, Some("http://monitor.onion".to_string()))
+                .render_site(&[result(hostile_site())], &history, Utc::now())
+                .unwrap()
+        };
+
+        write_site(&dir, &render(vec![FeedFormat::Atom, FeedFormat::Rss])).unwrap();
+        assert!(dir.join("atom.xml").exists() && dir.join("rss.xml").exists());
+
+        write_site(&dir, &render(vec![FeedFormat::Atom])).unwrap();
+        assert!(dir.join("atom.xml").exists());
+        assert!(!dir.join("rss.xml").exists());
+
+        write_site(&dir, &render(Vec::new())).unwrap();
+        assert!(!dir.join("atom.xml").exists());
+        assert!(dir.join("index.html").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reports_content_changes() {
+        let now = Utc::now();
+        let change = "Landing page replaced by a domain-for-sale page";
+        let mut history = HistoryDocument::default();
+        history.sites.insert("example".to_string(), vec![
+            record("online", now - Duration::hours(1)),
+            CheckRecord { content_change: Some(change.into()), ..record("online", now) },
+        ]);
+        let site = example_site(json!({
+            "content": {
+                "body_hash": "ab12", "simhash": "00ff00ff00ff00ff", "title": "Buy this domain", "length": 512,
+                "placeholder": "domain-for-sale page", "change": change, "checked_at": "2026-01-01T10:00:00Z"
+            }
+        }));
+
+        let files = Renderer::new(None).render_site(&[result(site)], &history, now).unwrap();
+        assert!(files[0].1.contains("⚠ Landing page looks like a domain-for-sale page"));
+        assert!(files[0].1.contains(&format!("⚠ {}", change)));
+
+        let page = &files[1].1;
+        assert!(page.contains("Landing Page"));
+        assert!(page.contains("<td>Buy this domain</td>"));
+        assert!(page.contains(&format!("Content: {}", change)));
+
+        let atom = &files.iter().find(|(path, _)| path == Path::new("atom.xml")).unwrap().1;
+        assert!(atom.contains("<title>Example changed its content</title>"));
+        assert!(atom.contains(change));
+    }
+}
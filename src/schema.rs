@@ -0,0 +1,136 @@
+use anyhow::{anyhow, bail, Result};
+use jsonschema::JSONSchema;
+use serde_json::Value;
+
+use crate::document::SitesDocument;
+use crate::history::HistoryDocument;
+use crate::output::FrontendDocument;
+
+const MAX_MESSAGE_CHARS: usize = 200;
+
+/// The data files this tool publishes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaKind {
+    /// `onions.json`
+    Sites,
+    /// `history.json`
+    History,
+    /// Frontend-optimised variant of `onions.json` (`FRONTEND_OUTPUTS`)
+    Frontend,
+}
+
+impl SchemaKind {
+    pub const ALL: &'static [SchemaKind] = &[SchemaKind::Sites, SchemaKind::History, SchemaKind::Frontend];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SchemaKind::Sites => "sites",
+            SchemaKind::History => "history",
+            SchemaKind::Frontend => "frontend",
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Self> {
+        Self::ALL.iter()
+            .copied()
+            .find(|kind| kind.name() == name)
+            .ok_or_else(|| anyhow!(
+                "Unknown schema '{}', expected one of: {}",
+                name,
+                Self::ALL.iter().map(|k| k.name()).collect::<Vec<_>>().join(", ")
+            ))
+    }
+}
+
+/// JSON Schema for `kind`, derived from the Rust types that read and write it
+pub fn schema_for(kind: SchemaKind) -> Value {
+    let schema = match kind {
+        SchemaKind::Sites => schemars::schema_for!(SitesDocument),
+        SchemaKind::History => schemars::schema_for!(HistoryDocument),
+        SchemaKind::Frontend => schemars::schema_for!(FrontendDocument),
+    };
+    serde_json::to_value(schema).expect("generated schemas are always valid JSON")
+}
+
+/// Validate `instance` against the schema for `kind`, returning one message per violation
+pub fn validate(kind: SchemaKind, instance: &Value) -> Result<Vec<String>> {
+    if kind == SchemaKind::Sites && instance.is_array() {
+        bail!("This is the legacy bare-array layout; the monitor migrates it on its next run");
+    }
+
+    let schema = schema_for(kind);
+    let compiled = match JSONSchema::compile(&schema) {
+        Ok(compiled) => compiled,
+        Err(e) => bail!("Failed to compile the {} schema: {}", kind.name(), e),
+    };
+
+    let errors = match compiled.validate(instance) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors
+            .map(|e| {
+                let path = e.instance_path.to_string();
+                let mut message = e.to_string();
+                // Messages embed the offending value, which can be an entire site
+                if message.chars().count() > MAX_MESSAGE_CHARS {
+                    message = message.chars().take(MAX_MESSAGE_CHARS).collect::<String>() + "…";
+                }
+                format!("{}: {}", if path.is_empty() { "/" } else { &path }, message)
+            })
+            .collect(),
+    };
+    Ok(errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use serde_json::json;
+
+    const SITE: &str = r#"{
+        "title": "Example Wallet",
+        "name": "example-wallet",
+        "onion_address": "http://wallet.onion",
+        "status": "online",
+        "prev_status": "offline",
+        "last_checked": "2024-05-01T12:00:00Z",
+        "tags": ["Wallet"]
+    }"#;
+
+    fn sites() -> SitesDocument {
+        SitesDocument::parse(&format!("[{}]", SITE)).unwrap()
+    }
+
+    #[test]
+    fn accepts_documents_written_by_the_monitor() {
+        let document = sites();
+        let mut history = HistoryDocument::default();
+        history.record(&document.sites, Utc::now());
+        let frontend = FrontendDocument::new(&document, &history, Utc::now());
+
+        for (kind, value) in [
+            (SchemaKind::Sites, serde_json::to_value(&document).unwrap()),
+            (SchemaKind::History, serde_json::to_value(&history).unwrap()),
+            (SchemaKind::Frontend, serde_json::to_value(&frontend).unwrap()),
+        ] {
+            assert_eq!(validate(kind, &value).unwrap(), Vec::<String>::new(), "{}", kind.name());
+        }
+    }
+
+    #[test]
+    fn rejects_the_legacy_array_with_a_migration_hint() {
+        let legacy: Value = serde_json::from_str(&format!("[{}]", SITE)).unwrap();
+        let error = validate(SchemaKind::Sites, &legacy).unwrap_err().to_string();
+        assert!(error.contains("migrates it on its next run"), "{}", error);
+    }
+
+    #[test]
+    fn reports_mistyped_fields_with_their_path() {
+        let mut value = serde_json::to_value(sites()).unwrap();
+        value["sites"][0]["tags"] = json!("x");
+
+        let errors = validate(SchemaKind::Sites, &value).unwrap();
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(errors[0].starts_with("/sites/0/tags: "), "{}", errors[0]);
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::HashSet;
+
+/// Turn a project name (or explicit id) into a lowercase, hyphen-separated slug.
+///
+/// Unicode letters and digits are kept (lowercased), apostrophes are dropped and
+/// every other run of punctuation/whitespace collapses into a single `-`.
+/// Falls back to `"site"` when nothing usable remains.
+pub fn slugify(input: &str) -> String {
+    let mut slug = String::with_capacity(input.len());
+    let mut pending_dash = false;
+
+    for c in input.chars() {
+        if c.is_alphanumeric() {
+            if pending_dash && !slug.is_empty() {
+                slug.push('-');
+            }
+            pending_dash = false;
+            slug.extend(c.to_lowercase());
+        } else if !matches!(c, '\'' | '\u{2019}') {
+            pending_dash = true;
+        }
+    }
+
+    if slug.is_empty() {
+        "site".to_string()
+    } else {
+        slug
+    }
+}
+
+/// Return `base` if it is free, otherwise the first of `base-2`, `base-3`, ... that is.
+pub fn disambiguate(base: &str, taken: &HashSet<String>) -> String {
+    if !taken.contains(base) {
+        return base.to_string();
+    }
+
+    (2..)
+        .map(|n| format!("{}-{}", base, n))
+        .find(|candidate| !taken.contains(candidate))
+        .expect("unbounded suffix search always finds a free slug")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugifies_names() {
+        assert_eq!(slugify("Tor Project"), "tor-project");
+        assert_eq!(slugify("  Wallet -- (Beta)!! "), "wallet-beta");
+        assert_eq!(slugify("Alice's Bridge"), "alices-bridge");
+        assert_eq!(slugify("Alice\u{2019}s Bridge"), "alices-bridge");
+        assert_eq!(slugify("v2.0_API"), "v2-0-api");
+        // Non-ASCII letters are kept and lowercased, symbols are dropped
+        assert_eq!(slugify("Ünïcode Café"), "ünïcode-café");
+        assert_eq!(slugify("🦊 Fox ✓ Wallet"), "fox-wallet");
+        assert_eq!(slugify("ΑΒΓ Δ"), "αβγ-δ");
+        assert_eq!(slugify("!!!"), "site");
+        assert_eq!(slugify(""), "site");
+    }
+
+    #[test]
+    fn suffixes_taken_slugs() {
+        let mut taken = HashSet::new();
+        assert_eq!(disambiguate("wallet", &taken), "wallet");
+
+        taken.insert("wallet".to_string());
+        assert_eq!(disambiguate("wallet", &taken), "wallet-2");
+        taken.insert("wallet-2".to_string());
+        taken.insert("wallet-4".to_string());
+        assert_eq!(disambiguate("wallet", &taken), "wallet-3");
+        assert_eq!(disambiguate("bridge", &taken), "bridge");
+    }
+}
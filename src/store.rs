@@ -0,0 +1,121 @@
+use anyhow::{bail, Context, Result};
+use std::fs::{self, File};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+/// Path of the backup kept next to `path`
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    path.with_file_name(name)
+}
+
+/// Read a file that may not exist yet; any other I/O error is reported
+pub fn read_optional(path: &Path) -> Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+/// Write `contents` to `path` so that readers only ever see the old or the new file.
+///
+/// The data goes to a temporary file in the same directory, is flushed to disk and
+/// then renamed over the target. The target must not be a bind-mounted file, as
+/// renaming over a mount point fails.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let Some(file_name) = path.file_name() else {
+        bail!("Invalid output path {}", path.display());
+    };
+
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(file_name);
+    tmp_name.push(format!(".tmp-{}", std::process::id()));
+    let tmp_path = dir.join(tmp_name);
+
+    let result = (|| {
+        let mut file = File::create(&tmp_path)
+            .with_context(|| format!("Failed to create {}", tmp_path.display()))?;
+        file.write_all(contents)
+            .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+        file.sync_all()
+            .with_context(|| format!("Failed to sync {}", tmp_path.display()))?;
+
+        fs::rename(&tmp_path, path)
+            .with_context(|| format!("Failed to move {} into place", path.display()))
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result?;
+
+    // Persist the rename itself
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+
+    Ok(())
+}
+
+/// Like [`write_atomic`], but first keeps the current file as `<path>.bak`. Call it once per
+/// run, for the first write, or the backup holds this run's data instead of the previous one.
+pub fn write_with_backup(path: &Path, contents: &[u8]) -> Result<()> {
+    if path.exists() {
+        let backup = backup_path(path);
+        fs::copy(path, &backup)
+            .with_context(|| format!("Failed to back up {} to {}", path.display(), backup.display()))?;
+    }
+
+    write_atomic(path, contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("onion-monitor-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn replaces_files_atomically() {
+        let dir = temp_dir("atomic");
+        let path = dir.join("onions.json");
+
+        write_atomic(&path, b"first").unwrap();
+        write_atomic(&path, b"second").unwrap();
+        assert_eq!(read_optional(&path).unwrap().as_deref(), Some("second"));
+        // No temporary files are left behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        assert!(write_atomic(&dir.join("missing").join("onions.json"), b"data").is_err());
+        assert_eq!(read_optional(&dir.join("history.json")).unwrap(), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_the_previous_file_as_backup() {
+        let dir = temp_dir("backup");
+        let path = dir.join("onions.json");
+        let backup = backup_path(&path);
+        assert_eq!(backup, dir.join("onions.json.bak"));
+
+        write_with_backup(&path, b"run 1").unwrap();
+        assert!(!backup.exists());
+
+        write_with_backup(&path, b"run 2").unwrap();
+        write_atomic(&path, b"run 2, checked").unwrap();
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "run 1");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "run 2, checked");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
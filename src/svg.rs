@@ -0,0 +1,195 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::history::{self, CheckRecord};
+
+const LINE_COLOR: &str = "#7d33b8";
+const FAILURE_COLOR: &str = "#dc2626";
+
+// Plot area inside the chart, leaving room for the axis labels
+const MARGIN_LEFT: f64 = 48.0;
+const MARGIN_RIGHT: f64 = 8.0;
+const MARGIN_TOP: f64 = 8.0;
+const MARGIN_BOTTOM: f64 = 20.0;
+
+/// Response-time chart over `records`, as inline SVG.
+///
+/// Successful checks are joined into a line that breaks across failed checks, which are
+/// marked along the bottom axis. Only numbers and formatted dates end up in the markup,
+/// so it can be embedded without escaping. Returns `None` when there is nothing to plot.
+pub fn latency_chart(records: &[CheckRecord], width: u32, height: u32) -> Option<String> {
+    let first = records.first()?.checked_at;
+    let last = records.last()?.checked_at;
+    let max_ms = records.iter().filter_map(|r| r.response_time_ms).max()?;
+    let y_max = nice_ceiling(max_ms);
+
+    let (width, height) = (width as f64, height as f64);
+    let plot_width = width - MARGIN_LEFT - MARGIN_RIGHT;
+    let plot_height = height - MARGIN_TOP - MARGIN_BOTTOM;
+    let bottom = MARGIN_TOP + plot_height;
+    let span = (last - first).num_seconds().max(1) as f64;
+
+    let x = |at: DateTime<Utc>| MARGIN_LEFT + (at - first).num_seconds() as f64 / span * plot_width;
+    let y = |ms: u64| bottom - ms as f64 / y_max as f64 * plot_height;
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg class="latency-chart" xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {w} {h}" width="{w}" height="{h}" role="img" aria-label="Response time, {from} to {to}">"#,
+        w = width,
+        h = height,
+        from = first.format("%b %d"),
+        to = last.format("%b %d"),
+    );
+
+    // Axes and labels
+    let _ = write!(
+        svg,
+        r#"<path class="chart-axis" d="M{l} {t}V{b}H{r}" fill="none" stroke="currentColor" stroke-opacity="0.3"/>"#,
+        l = MARGIN_LEFT,
+        t = MARGIN_TOP,
+        b = bottom,
+        r = width - MARGIN_RIGHT,
+    );
+    let _ = write!(
+        svg,
+        r#"<g class="chart-labels" font-size="10" fill="currentColor" fill-opacity="0.7"><text x="{lx}" y="{ty}" text-anchor="end">{y_max}ms</text><text x="{lx}" y="{by}" text-anchor="end">0</text><text x="{l}" y="{dy}">{from}</text><text x="{r}" y="{dy}" text-anchor="end">{to}</text></g>"#,
+        lx = MARGIN_LEFT - 4.0,
+        ty = MARGIN_TOP + 8.0,
+        by = bottom,
+        l = MARGIN_LEFT,
+        r = width - MARGIN_RIGHT,
+        dy = height - 4.0,
+        from = first.format("%b %d %H:%M"),
+        to = last.format("%b %d %H:%M"),
+    );
+
+    let points: Vec<_> = records.iter()
+        .map(|r| r.response_time_ms.map(|ms| (x(r.checked_at), y(ms))))
+        .collect();
+    write_line(&mut svg, &points, 1.5);
+
+    // Failed checks
+    for record in records.iter().filter(|r| r.response_time_ms.is_none() && r.status != "unknown") {
+        let _ = write!(
+            svg,
+            r#"<line x1="{x:.1}" y1="{b}" x2="{x:.1}" y2="{t}" stroke="{FAILURE_COLOR}" stroke-width="1.5"/>"#,
+            x = x(record.checked_at),
+            b = bottom,
+            t = bottom - 6.0,
+        );
+    }
+
+    svg.push_str("</svg>");
+    Some(svg)
+}
+
+/// Response times of the last `max_points` checks as a small axis-less line, or `None`
+/// if none of them succeeded
+pub fn sparkline(records: &[CheckRecord], max_points: usize, width: u32, height: u32) -> Option<String> {
+    let recent = &records[records.len().saturating_sub(max_points)..];
+    let max_ms = recent.iter().filter_map(|r| r.response_time_ms).max()?.max(1);
+
+    // Inset by the stroke width so the line is not clipped at the edges
+    let (width, height) = (width as f64, height as f64);
+    let step = (width - 2.0) / (recent.len().max(2) - 1) as f64;
+    let points: Vec<_> = recent.iter()
+        .enumerate()
+        .map(|(i, r)| r.response_time_ms.map(|ms| {
+            (1.0 + i as f64 * step, height - 1.0 - ms as f64 / max_ms as f64 * (height - 2.0))
+        }))
+        .collect();
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg class="sparkline" xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {w} {h}" width="{w}" height="{h}" role="img" aria-label="Response time of the last {n} checks, up to {max_ms}ms">"#,
+        w = width,
+        h = height,
+        n = recent.len(),
+    );
+    write_line(&mut svg, &points, 1.0);
+    svg.push_str("</svg>");
+    Some(svg)
+}
+
+/// One segment per day for the `days` days up to `now`, coloured by that day's availability
+pub fn uptime_bar(records: &[CheckRecord], now: DateTime<Utc>, days: u32, width: u32, height: u32) -> String {
+    let mut by_day: BTreeMap<NaiveDate, Vec<&CheckRecord>> = BTreeMap::new();
+    for record in records.iter().filter(|r| r.status != "unknown") {
+        by_day.entry(record.checked_at.date_naive()).or_default().push(record);
+    }
+
+    let (width, height) = (width as f64, height as f64);
+    let slot = width / days as f64;
+    let gap = if slot > 3.0 { 1.0 } else { 0.0 };
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg class="uptime-bar" xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {w} {h}" width="{w}" height="{h}" preserveAspectRatio="none" role="img" aria-label="Daily availability over the last {days} days">"#,
+        w = width,
+        h = height,
+    );
+
+    let today = now.date_naive();
+    for i in 0..days {
+        let day = today - Duration::days((days - 1 - i) as i64);
+        let availability = by_day.get(&day).map(|checks| {
+            checks.iter().map(|r| history::availability(&r.status)).sum::<f64>() / checks.len() as f64 * 100.0
+        });
+        let (color, label) = match availability {
+            None => ("#cbd5e0", "no data".to_string()),
+            Some(pct) if pct >= 99.0 => ("#48bb78", format!("{:.1}%", pct)),
+            Some(pct) if pct >= 90.0 => ("#ed8936", format!("{:.1}%", pct)),
+            Some(pct) => ("#f56565", format!("{:.1}%", pct)),
+        };
+        let _ = write!(
+            svg,
+            r#"<rect x="{x:.2}" y="0" width="{w:.2}" height="{h}" rx="1" fill="{color}"><title>{day}: {label}</title></rect>"#,
+            x = i as f64 * slot,
+            w = slot - gap,
+            h = height,
+            day = day.format("%b %d"),
+        );
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Join consecutive points into polylines, breaking the line at `None` (failed checks)
+fn write_line(svg: &mut String, points: &[Option<(f64, f64)>], stroke_width: f64) {
+    for segment in points.split(Option::is_none) {
+        let segment: Vec<(f64, f64)> = segment.iter().flatten().copied().collect();
+        match segment.as_slice() {
+            [] => {}
+            [(x, y)] => {
+                let _ = write!(svg, r#"<circle cx="{:.1}" cy="{:.1}" r="{}" fill="{LINE_COLOR}"/>"#, x, y, stroke_width + 0.5);
+            }
+            _ => {
+                let coords: Vec<String> = segment.iter().map(|(x, y)| format!("{:.1},{:.1}", x, y)).collect();
+                let _ = write!(
+                    svg,
+                    r#"<polyline points="{}" fill="none" stroke="{LINE_COLOR}" stroke-width="{}" stroke-linejoin="round"/>"#,
+                    coords.join(" "),
+                    stroke_width,
+                );
+            }
+        }
+    }
+}
+
+/// Round `ms` up to a readable axis maximum (1, 2 or 5 times a power of ten)
+fn nice_ceiling(ms: u64) -> u64 {
+    let mut magnitude = 1;
+    loop {
+        for step in [1, 2, 5] {
+            if ms <= step * magnitude {
+                return step * magnitude;
+            }
+        }
+        magnitude *= 10;
+    }
+}
@@ -0,0 +1,127 @@
+use chrono::NaiveTime;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// `--write-out` format whose output `parse_curl` expects
+pub const CURL_WRITE_OUT: &str =
+    "%{http_code} %{time_connect} %{time_appconnect} %{time_pretransfer} %{time_starttransfer} %{time_total}";
+
+/// Where the time of a check went, in milliseconds. Phases that did not happen (TLS for
+/// plain HTTP) or could not be told apart are `None`.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Default, PartialEq, Eq)]
+pub struct Timings {
+    /// Connecting to the proxy and negotiating SOCKS, until the CONNECT request is sent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub socks_handshake_ms: Option<u64>,
+    /// Waiting for the proxy to accept the CONNECT request: Tor fetching the descriptor and
+    /// completing the introduction and rendezvous
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub circuit_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_ms: Option<u64>,
+    /// From sending the request to the first byte of the response, i.e. the server's latency
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttfb_ms: Option<u64>,
+    pub total_ms: u64,
+}
+
+/// Status code and timings from curl's `--write-out` output (`stdout`, in `CURL_WRITE_OUT`
+/// format) and its `--verbose --trace-time` log (`stderr`). Every phase comes from the
+/// write-out timers, which share curl's start as their origin; the log only tells how the
+/// connect time splits into the SOCKS handshake and the wait for the circuit.
+pub fn parse_curl(stdout: &str, stderr: &str) -> Option<(u16, Timings)> {
+    let fields: Vec<&str> = stdout.split_whitespace().collect();
+    let [code, connect, appconnect, pretransfer, starttransfer, total] = fields[..] else {
+        return None;
+    };
+    let code: u16 = code.parse().ok()?;
+    let seconds = |value: &str| value.parse::<f64>().ok().filter(|s| *s > 0.0);
+    let (connect, appconnect) = (seconds(connect), seconds(appconnect));
+    let (pretransfer, starttransfer) = (seconds(pretransfer), seconds(starttransfer));
+    let total = seconds(total).unwrap_or_default();
+
+    let ms = |seconds: f64| (seconds.max(0.0) * 1000.0).round() as u64;
+    let circuit = socks_wait(stderr).zip(connect).map(|(wait, connect)| wait.min(connect));
+    let timings = Timings {
+        socks_handshake_ms: circuit.zip(connect).map(|(circuit, connect)| ms(connect - circuit)),
+        circuit_ms: circuit.map(ms),
+        tls_ms: appconnect.zip(connect).map(|(tls_done, connect)| ms(tls_done - connect)),
+        ttfb_ms: pretransfer.zip(starttransfer).map(|(sent, first_byte)| ms(first_byte - sent)),
+        total_ms: ms(total),
+    };
+    Some((code, timings))
+}
+
+/// Seconds between sending the SOCKS CONNECT request and the proxy granting it, from the
+/// timestamps of the `--trace-time` log
+fn socks_wait(stderr: &str) -> Option<f64> {
+    let mut request_sent = None;
+    let mut granted = None;
+    for line in stderr.lines() {
+        let Some((time, message)) = line.split_once(' ') else {
+            continue;
+        };
+        let Ok(time) = NaiveTime::parse_from_str(time, "%H:%M:%S%.f") else {
+            continue;
+        };
+        if message.contains("SOCKS5 connect to") || message.contains("SOCKS4 communication to") {
+            request_sent.get_or_insert(time);
+        } else if message.contains("request granted") {
+            granted.get_or_insert(time);
+        }
+    }
+    // Around midnight the clock wraps
+    request_sent.zip(granted).map(|(sent, granted)| {
+        (granted - sent).num_microseconds().unwrap_or_default().rem_euclid(86_400_000_000) as f64 / 1e6
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_a_check_into_phases() {
+        let stderr = "\
+23:59:59.900000 *   Trying 127.0.0.1:9050...
+23:59:59.900600 * Connected to 127.0.0.1 (127.0.0.1) port 9050 (#0)
+23:59:59.950000 * SOCKS5 connect to abc.onion:443 (remotely resolved)
+00:00:02.150000 * SOCKS5 request granted.
+00:00:02.150100 * Connected to 127.0.0.1 (127.0.0.1) port 9050 (#0)
+00:00:02.900000 * SSL connection using TLSv1.3 / TLS_AES_256_GCM_SHA384
+00:00:02.900100 > GET / HTTP/1.1
+00:00:03.300000 < HTTP/1.1 200 OK
+";
+        // curl started 300ms before its first log line, which must not shift any phase
+        let (code, timings) = parse_curl("200 2.550000 3.300000 3.300100 3.700000 3.700500", stderr).unwrap();
+        assert_eq!(code, 200);
+        assert_eq!(timings, Timings {
+            socks_handshake_ms: Some(350),
+            circuit_ms: Some(2200),
+            tls_ms: Some(750),
+            ttfb_ms: Some(400),
+            total_ms: 3701,
+        });
+    }
+
+    #[test]
+    fn leaves_out_phases_it_cannot_see() {
+        // Plain HTTP, and a log without the SOCKS messages
+        let (code, timings) = parse_curl("301 1.200000 0.000000 1.300000 1.800000 1.800100", "").unwrap();
+        assert_eq!(code, 301);
+        assert_eq!(timings, Timings { ttfb_ms: Some(500), total_ms: 1800, ..Timings::default() });
+
+        // HTTPS without the SOCKS messages still has its TLS handshake
+        let (_, timings) = parse_curl("200 1.000000 1.400000 1.400100 1.900000 1.900100", "").unwrap();
+        assert_eq!(timings.tls_ms, Some(400));
+        assert_eq!(timings.circuit_ms, None);
+
+        // A failed connection
+        let (code, timings) = parse_curl("000 0.000000 0.000000 0.000000 0.000000 60.001000", "").unwrap();
+        assert_eq!(code, 0);
+        assert_eq!(timings.total_ms, 60001);
+        assert_eq!(timings.ttfb_ms, None);
+
+        assert!(parse_curl("200", "").is_none());
+    }
+}
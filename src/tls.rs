@@ -0,0 +1,233 @@
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Warn this many days before a certificate expires, unless overridden by `CERT_EXPIRY_WARNING_DAYS`
+const DEFAULT_EXPIRY_WARNING_DAYS: i64 = 14;
+
+/// `--write-out` format whose output `parse_curl` expects. Certificates are verified even
+/// though the check passes `--insecure`; the result just does not fail the request.
+pub const CURL_WRITE_OUT: &str = "%{ssl_verify_result}\n%{certs}";
+
+/// The certificate an HTTPS onion service presented
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct CertificateCheck {
+    pub subject: String,
+    /// DNS names from the subjectAltName extension
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sans: Vec<String>,
+    pub issuer: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_before: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_after: Option<DateTime<Utc>>,
+    pub self_signed: bool,
+    /// Whether the certificate names the onion hostname
+    pub covers_host: bool,
+    /// Whether it chains to a CA trusted by the system
+    pub trusted: bool,
+    /// OpenSSL's verification error, when not trusted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verify_error: Option<String>,
+    /// Problems that break clients verifying the certificate (expiry, wrong host, untrusted CA)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Days of notice before a certificate expires, from `CERT_EXPIRY_WARNING_DAYS`
+pub fn expiry_warning_days_from_env() -> i64 {
+    match std::env::var("CERT_EXPIRY_WARNING_DAYS") {
+        Ok(value) => value.trim().parse().ok().filter(|d| *d >= 0).unwrap_or_else(|| {
+            println!("⚠ Warning: Invalid CERT_EXPIRY_WARNING_DAYS '{}', using {}", value, DEFAULT_EXPIRY_WARNING_DAYS);
+            DEFAULT_EXPIRY_WARNING_DAYS
+        }),
+        Err(_) => DEFAULT_EXPIRY_WARNING_DAYS,
+    }
+}
+
+/// Inspect the leaf certificate in curl's `--write-out` output (in `CURL_WRITE_OUT` format)
+/// for a request to `host`. `None` for plain HTTP or when curl did not report certificates.
+pub fn parse_curl(output: &str, host: &str, warning_days: i64, now: DateTime<Utc>) -> Option<CertificateCheck> {
+    let (verify_result, certs) = output.split_once('\n')?;
+    let verify_result: i64 = verify_result.trim().parse().ok()?;
+
+    let mut subject = None;
+    let mut issuer = None;
+    let mut sans = Vec::new();
+    let mut not_before = None;
+    let mut not_after = None;
+    // The leaf comes first; stop at the next certificate in the chain
+    for line in certs.lines() {
+        if line.starts_with("-----END CERTIFICATE-----") {
+            break;
+        }
+        let Some((field, value)) = line.split_once(':') else {
+            continue;
+        };
+        match field {
+            "Subject" => subject = Some(value.trim().to_string()),
+            "Issuer" => issuer = Some(value.trim().to_string()),
+            "X509v3 Subject Alternative Name" => {
+                sans = value.split(',').filter_map(|name| name.trim().strip_prefix("DNS:")).map(str::to_string).collect();
+            }
+            "Start date" => not_before = parse_date(value),
+            "Expire date" => not_after = parse_date(value),
+            _ => {}
+        }
+    }
+    let subject = subject?;
+    let issuer = issuer.unwrap_or_default();
+
+    // Only fall back to the common name when there are no SANs, like browsers
+    let covers_host = if sans.is_empty() {
+        common_name(&subject).is_some_and(|cn| matches_host(cn, host))
+    } else {
+        sans.iter().any(|name| matches_host(name, host))
+    };
+    let self_signed = matches!(verify_result, 18 | 19) || subject == issuer;
+    let verify_error = (verify_result != 0).then(|| verify_error(verify_result));
+
+    let mut warnings = Vec::new();
+    match not_after {
+        Some(expires) if expires <= now => {
+            warnings.push(format!("Certificate expired on {}", expires.format("%Y-%m-%d")));
+        }
+        Some(expires) if expires - now < Duration::days(warning_days) => {
+            warnings.push(format!(
+                "Certificate expires on {} (in {} days)",
+                expires.format("%Y-%m-%d"),
+                (expires - now).num_days()
+            ));
+        }
+        _ => {}
+    }
+    if not_before.is_some_and(|start| start > now) {
+        warnings.push("Certificate is not valid yet".to_string());
+    }
+    if !covers_host {
+        warnings.push(format!("Certificate does not cover {}", host));
+    }
+    // Self-signed certificates are common on onions, where the address already authenticates
+    // the service; a CA-issued one that fails to verify is a misconfiguration
+    if !self_signed && verify_result != 0 && !matches!(verify_result, 9 | 10) {
+        warnings.push(format!("Certificate is not trusted: {}", verify_error.as_deref().unwrap_or_default()));
+    }
+
+    Some(CertificateCheck {
+        subject,
+        sans,
+        issuer,
+        not_before,
+        not_after,
+        self_signed,
+        covers_host,
+        trusted: verify_result == 0,
+        verify_error,
+        warnings,
+        checked_at: now,
+    })
+}
+
+/// Dates as OpenSSL prints them, e.g. `Jan  1 00:00:00 2026 GMT`
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+    NaiveDateTime::parse_from_str(&value, "%b %d %H:%M:%S %Y GMT").ok().map(|d| d.and_utc())
+}
+
+fn common_name(subject: &str) -> Option<&str> {
+    subject.split(',').find_map(|part| {
+        let (key, value) = part.split_once('=')?;
+        (key.trim() == "CN").then(|| value.trim())
+    })
+}
+
+/// Match a certificate name against `host`; a wildcard covers exactly one label
+fn matches_host(name: &str, host: &str) -> bool {
+    let (name, host) = (name.to_ascii_lowercase(), host.to_ascii_lowercase());
+    match name.strip_prefix("*.") {
+        Some(domain) => host.split_once('.').is_some_and(|(label, rest)| !label.is_empty() && rest == domain),
+        None => name == host,
+    }
+}
+
+/// Description of the OpenSSL verification errors HTTPS onions run into
+fn verify_error(code: i64) -> String {
+    let reason = match code {
+        2 | 20 => "issuer certificate not found",
+        9 => "certificate not yet valid",
+        10 => "certificate expired",
+        18 => "self-signed certificate",
+        19 => "self-signed certificate in chain",
+        21 => "unable to verify the first certificate",
+        23 => "certificate revoked",
+        _ => return format!("verification error {}", code),
+    };
+    reason.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const HOST: &str = "abc.onion";
+
+    fn output(verify_result: i64, subject: &str, issuer: &str, sans: &str, expires: &str) -> String {
+        format!(
+            "{}\nSubject:{}\nIssuer:{}\nVersion:2\nX509v3 Subject Alternative Name:{}\nStart date:Jan  1 00:00:00 2026 GMT\nExpire date:{}\nCert:\n-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----\nSubject:CN = Intermediate\nIssuer:CN = Root\n",
+            verify_result, subject, issuer, sans, expires
+        )
+    }
+
+    #[test]
+    fn inspects_the_leaf_certificate() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 1, 0, 0, 0).unwrap();
+        let out = output(0, "CN = abc.onion", "C = US, O = HARICA, CN = HARICA DV", "DNS:abc.onion, DNS:*.abc.onion", "Dec 31 23:59:59 2026 GMT");
+        let check = parse_curl(&out, "www.abc.onion", 14, now).unwrap();
+        assert_eq!(check.subject, "CN = abc.onion");
+        assert_eq!(check.issuer, "C = US, O = HARICA, CN = HARICA DV");
+        assert_eq!(check.sans, ["abc.onion", "*.abc.onion"]);
+        assert_eq!(check.not_before, Some(Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap()));
+        assert_eq!(check.not_after, Some(Utc.with_ymd_and_hms(2026, 12, 31, 23, 59, 59).unwrap()));
+        assert!(check.trusted && check.covers_host && !check.self_signed);
+        assert!(check.warnings.is_empty(), "{:?}", check.warnings);
+
+        // Plain HTTP reports no certificate
+        assert!(parse_curl("0\n", HOST, 14, now).is_none());
+    }
+
+    #[test]
+    fn warns_before_expiry_and_about_the_wrong_host() {
+        let now = Utc.with_ymd_and_hms(2026, 12, 25, 0, 0, 0).unwrap();
+        let out = output(0, "CN = abc.onion", "CN = HARICA DV", "DNS:other.onion", "Dec 31 23:59:59 2026 GMT");
+        let check = parse_curl(&out, HOST, 14, now).unwrap();
+        assert_eq!(check.warnings, [
+            "Certificate expires on 2026-12-31 (in 6 days)",
+            "Certificate does not cover abc.onion",
+        ]);
+        assert!(parse_curl(&out, HOST, 3, now).unwrap().warnings.len() == 1);
+
+        let out = output(10, "CN = abc.onion", "CN = HARICA DV", "DNS:abc.onion", "Dec 31 23:59:59 2026 GMT");
+        let check = parse_curl(&out, HOST, 14, Utc.with_ymd_and_hms(2027, 1, 2, 0, 0, 0).unwrap()).unwrap();
+        assert!(!check.trusted);
+        assert_eq!(check.verify_error.as_deref(), Some("certificate expired"));
+        assert_eq!(check.warnings, ["Certificate expired on 2026-12-31"]);
+    }
+
+    #[test]
+    fn accepts_self_signed_certificates() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 1, 0, 0, 0).unwrap();
+        let out = output(18, "CN = abc.onion", "CN = abc.onion", "", "Dec 31 23:59:59 2026 GMT");
+        let check = parse_curl(&out, HOST, 14, now).unwrap();
+        assert!(check.self_signed && !check.trusted);
+        assert!(check.covers_host, "falls back to the common name");
+        assert!(check.warnings.is_empty(), "{:?}", check.warnings);
+
+        // A CA-issued certificate missing its intermediate is a problem
+        let out = output(20, "CN = abc.onion", "CN = HARICA DV", "DNS:abc.onion", "Dec 31 23:59:59 2026 GMT");
+        let check = parse_curl(&out, HOST, 14, now).unwrap();
+        assert_eq!(check.warnings, ["Certificate is not trusted: issuer certificate not found"]);
+        assert!(!matches_host("*.abc.onion", HOST));
+    }
+}
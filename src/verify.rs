@@ -0,0 +1,631 @@
+use chrono::{DateTime, Utc};
+use reqwest::{Client, Url};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+/// Upper bound on how much of a clearnet page we scan for the meta tag
+const MAX_BODY_BYTES: usize = 512 * 1024;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum VerificationStatus {
+    /// The clearnet side vouches for one of the monitored onion addresses
+    Verified,
+    /// Only the onion names the clearnet domain, which any mirror could do
+    Claimed,
+    /// The clearnet side vouches for a different onion address
+    Mismatch,
+    /// Nothing was published that links the onion to the clearnet side
+    Unverified,
+    /// The clearnet side could not be fetched
+    Error,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct OnionLocationCheck {
+    pub status: VerificationStatus,
+    /// Onion URL advertised by the clearnet site, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub advertised: Option<String>,
+    /// Where the advertisement was found: `header` or `meta`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Clearnet file listing the onion addresses that belong to the domain, one per line
+const WELL_KNOWN_ONIONS_PATH: &str = "/.well-known/onion-services.txt";
+/// Onion-side file naming the clearnet domain(s) the service belongs to
+const WELL_KNOWN_DOMAINS_PATH: &str = "/.well-known/clearnet-domains.txt";
+/// DNS-over-HTTPS JSON endpoint used for TXT lookups
+const DEFAULT_DOH_URL: &str = "https://cloudflare-dns.com/dns-query";
+
+/// A published proof that ties an onion address to a clearnet domain
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProofMethod {
+    /// `WELL_KNOWN_ONIONS_PATH` on the clearnet website lists the onion
+    WellKnown,
+    /// `WELL_KNOWN_DOMAINS_PATH` on the onion service names the clearnet domain
+    OnionBacklink,
+    /// A `onion=<address>` TXT record on the website's hostname
+    Dns,
+}
+
+impl ProofMethod {
+    /// Parse the comma-separated `OWNERSHIP_PROOFS` list; unset means disabled
+    pub fn from_env() -> Vec<ProofMethod> {
+        let Ok(value) = std::env::var("OWNERSHIP_PROOFS") else {
+            return Vec::new();
+        };
+
+        value.split(',')
+            .map(str::trim)
+            .filter(|m| !m.is_empty())
+            .filter_map(|m| match m {
+                "well-known" => Some(ProofMethod::WellKnown),
+                "onion-backlink" => Some(ProofMethod::OnionBacklink),
+                "dns" => Some(ProofMethod::Dns),
+                other => {
+                    println!("⚠ Warning: Unknown ownership proof method '{}'", other);
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct OwnershipCheck {
+    pub status: VerificationStatus,
+    /// Proof that established the result, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<ProofMethod>,
+    /// Outcome of every attempted method, for troubleshooting
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<String>,
+    pub checked_at: DateTime<Utc>,
+    /// Last time ownership was successfully verified
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verified_at: Option<DateTime<Utc>>,
+}
+
+/// Outcome of a single proof method
+enum ProofOutcome {
+    Matches,
+    Conflicts,
+    NotPublished,
+    Failed(String),
+}
+
+/// How clearnet counterparts are fetched, from `CLEARNET_MODE`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClearnetMode {
+    /// Through the same SOCKS proxy as the onion checks (default)
+    Proxy,
+    /// Directly from the monitor host
+    Direct,
+    /// Skip clearnet checks entirely
+    Off,
+}
+
+impl ClearnetMode {
+    pub fn from_env() -> Self {
+        match std::env::var("CLEARNET_MODE").as_deref() {
+            Ok("direct") => ClearnetMode::Direct,
+            Ok("off") => ClearnetMode::Off,
+            Ok("proxy") | Err(_) => ClearnetMode::Proxy,
+            Ok(other) => {
+                println!("⚠ Warning: Unknown CLEARNET_MODE '{}', using proxy", other);
+                ClearnetMode::Proxy
+            }
+        }
+    }
+}
+
+/// Build the HTTP client used for clearnet requests
+pub fn clearnet_client(mode: ClearnetMode, proxy_url: &str) -> reqwest::Result<Client> {
+    let mut builder = Client::builder()
+        .timeout(Duration::from_secs(60))
+        .user_agent("onion-monitoring-tool");
+
+    if mode == ClearnetMode::Proxy {
+        builder = builder.proxy(reqwest::Proxy::all(proxy_url)?);
+    }
+
+    builder.build()
+}
+
+/// Build the HTTP client used to fetch proof files from onion services
+pub fn onion_client(proxy_url: &str) -> reqwest::Result<Client> {
+    Client::builder()
+        .timeout(Duration::from_secs(60))
+        .user_agent("onion-monitoring-tool")
+        .proxy(reqwest::Proxy::all(proxy_url)?)
+        // Onion services commonly use self-signed certificates
+        .danger_accept_invalid_certs(true)
+        .build()
+}
+
+/// Fetch `website` and compare its `Onion-Location` against the monitored addresses
+pub async fn check_onion_location(client: &Client, website: &str, onion_urls: &[String]) -> OnionLocationCheck {
+    let mut check = OnionLocationCheck {
+        status: VerificationStatus::Unverified,
+        advertised: None,
+        source: None,
+        error: None,
+        checked_at: Utc::now(),
+    };
+
+    let response = match client.get(website).send().await {
+        Ok(response) => response,
+        Err(e) => {
+            check.status = VerificationStatus::Error;
+            check.error = Some(e.to_string());
+            return check;
+        }
+    };
+    // Error pages from the site or a CDN in front of it do not speak for the site
+    if !response.status().is_success() {
+        check.status = VerificationStatus::Error;
+        check.error = Some(format!("HTTP {}", response.status()));
+        return check;
+    }
+
+    let header = response.headers()
+        .get("onion-location")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_string());
+
+    let advertised = match header {
+        Some(location) => Some((location, "header")),
+        None => meta_onion_location(&read_body_prefix(response).await)
+            .map(|location| (location, "meta")),
+    };
+
+    if let Some((location, source)) = advertised {
+        check.status = if onion_urls.iter().any(|url| same_host(url, &location)) {
+            VerificationStatus::Verified
+        } else {
+            VerificationStatus::Mismatch
+        };
+        check.advertised = Some(location);
+        check.source = Some(source.to_string());
+    }
+
+    check
+}
+
+/// Try each configured proof method and report whether the onion belongs to `website`.
+///
+/// Proofs are trusted as far as the transport is: the clearnet proofs rely on TLS/DNS
+/// control of the domain. The backlink only shows what the onion claims about itself, so
+/// on its own it yields `Claimed`, never `Verified`.
+pub async fn check_ownership(
+    clearnet: &Client,
+    onion: &Client,
+    methods: &[ProofMethod],
+    website: &str,
+    onion_urls: &[String],
+    previous: Option<&OwnershipCheck>,
+) -> OwnershipCheck {
+    let onion_hosts: HashSet<String> = onion_urls.iter().filter_map(|u| host_of(u)).collect();
+    let domain = host_of(website).unwrap_or_default();
+
+    let mut outcomes = Vec::new();
+    for &method in methods {
+        let outcome = match method {
+            ProofMethod::WellKnown => {
+                match fetch_proof(clearnet, website, WELL_KNOWN_ONIONS_PATH).await {
+                    Ok(Some(text)) => match_onion_list(&text, &onion_hosts),
+                    Ok(None) => ProofOutcome::NotPublished,
+                    Err(e) => ProofOutcome::Failed(e),
+                }
+            }
+            ProofMethod::OnionBacklink => {
+                let mut outcome = ProofOutcome::NotPublished;
+                for url in onion_urls {
+                    outcome = match fetch_proof(onion, url, WELL_KNOWN_DOMAINS_PATH).await {
+                        Ok(Some(text)) => match_domain_list(&text, &domain),
+                        Ok(None) => ProofOutcome::NotPublished,
+                        Err(e) => ProofOutcome::Failed(e),
+                    };
+                    if matches!(outcome, ProofOutcome::Matches) {
+                        break;
+                    }
+                }
+                outcome
+            }
+            ProofMethod::Dns => match lookup_txt(clearnet, &domain).await {
+                Ok(records) => {
+                    let claims: Vec<&str> = records.iter()
+                        .filter_map(|r| r.trim().strip_prefix("onion="))
+                        .collect();
+                    if claims.is_empty() {
+                        ProofOutcome::NotPublished
+                    } else {
+                        match_onion_list(&claims.join("\n"), &onion_hosts)
+                    }
+                }
+                Err(e) => ProofOutcome::Failed(e),
+            },
+        };
+
+        // A clearnet proof settles it; a backlink still needs one
+        let settled = method != ProofMethod::OnionBacklink && matches!(outcome, ProofOutcome::Matches);
+        outcomes.push((method, outcome));
+        if settled {
+            break;
+        }
+    }
+
+    let mut check = OwnershipCheck {
+        status: VerificationStatus::Unverified,
+        method: None,
+        details: Vec::new(),
+        checked_at: Utc::now(),
+        verified_at: previous.and_then(|p| p.verified_at),
+    };
+    conclude(&mut check, outcomes, methods.len());
+    check
+}
+
+/// Derive the status of `check` from the outcome of each attempted method
+fn conclude(check: &mut OwnershipCheck, outcomes: Vec<(ProofMethod, ProofOutcome)>, attempted: usize) {
+    let mut conflicts = false;
+    let mut claimed = false;
+    let mut failures = 0;
+
+    for (method, outcome) in outcomes {
+        let label = serde_json::to_value(method).ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default();
+        match outcome {
+            ProofOutcome::Matches if method == ProofMethod::OnionBacklink => {
+                check.details.push(format!("{}: claimed by the onion", label));
+                claimed = true;
+            }
+            ProofOutcome::Matches => {
+                check.details.push(format!("{}: verified", label));
+                check.status = VerificationStatus::Verified;
+                check.method = Some(method);
+                check.verified_at = Some(check.checked_at);
+                return;
+            }
+            ProofOutcome::Conflicts => {
+                check.details.push(format!("{}: lists other onion addresses", label));
+                conflicts = true;
+            }
+            ProofOutcome::NotPublished => check.details.push(format!("{}: not published", label)),
+            ProofOutcome::Failed(e) => {
+                check.details.push(format!("{}: {}", label, e));
+                failures += 1;
+            }
+        }
+    }
+
+    check.status = if conflicts {
+        VerificationStatus::Mismatch
+    } else if claimed {
+        check.method = Some(ProofMethod::OnionBacklink);
+        VerificationStatus::Claimed
+    } else if failures > 0 && failures == attempted {
+        VerificationStatus::Error
+    } else {
+        VerificationStatus::Unverified
+    };
+}
+
+/// GET `path` relative to the origin of `base`; `Ok(None)` when the file does not exist
+async fn fetch_proof(client: &Client, base: &str, path: &str) -> Result<Option<String>, String> {
+    let url = Url::parse(base)
+        .and_then(|u| u.join(path))
+        .map_err(|e| format!("invalid URL: {}", e))?;
+
+    let response = client.get(url).send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Ok(None);
+    }
+
+    Ok(Some(read_body_prefix(response).await))
+}
+
+fn match_onion_list(text: &str, onion_hosts: &HashSet<String>) -> ProofOutcome {
+    let listed: Vec<String> = text.split_whitespace()
+        .filter(|token| token.contains(".onion"))
+        .filter_map(host_of)
+        .collect();
+
+    if listed.iter().any(|host| onion_hosts.contains(host)) {
+        ProofOutcome::Matches
+    } else if listed.is_empty() {
+        ProofOutcome::NotPublished
+    } else {
+        ProofOutcome::Conflicts
+    }
+}
+
+fn match_domain_list(text: &str, domain: &str) -> ProofOutcome {
+    let listed: Vec<String> = text.split_whitespace().filter_map(host_of).collect();
+    let apex = domain.strip_prefix("www.").unwrap_or(domain);
+
+    if listed.iter().any(|host| host == domain || host == apex) {
+        ProofOutcome::Matches
+    } else if listed.is_empty() {
+        ProofOutcome::NotPublished
+    } else {
+        ProofOutcome::Conflicts
+    }
+}
+
+#[derive(Deserialize)]
+struct DohResponse {
+    #[serde(rename = "Status")]
+    status: u32,
+    #[serde(rename = "Answer", default)]
+    answer: Vec<DohAnswer>,
+}
+
+#[derive(Deserialize)]
+struct DohAnswer {
+    #[serde(rename = "type")]
+    record_type: u16,
+    data: String,
+}
+
+/// Resolve TXT records through a DNS-over-HTTPS JSON endpoint (`DOH_URL`)
+async fn lookup_txt(client: &Client, domain: &str) -> Result<Vec<String>, String> {
+    const TXT: u16 = 16;
+    let doh_url = std::env::var("DOH_URL").unwrap_or_else(|_| DEFAULT_DOH_URL.to_string());
+
+    let response: DohResponse = client.get(&doh_url)
+        .query(&[("name", domain), ("type", "TXT")])
+        .header("accept", "application/dns-json")
+        .send().await
+        .map_err(|e| e.to_string())?
+        .json().await
+        .map_err(|e| format!("invalid DoH response: {}", e))?;
+
+    if response.status != 0 {
+        return Ok(Vec::new());
+    }
+
+    Ok(response.answer.into_iter()
+        .filter(|a| a.record_type == TXT)
+        // TXT data comes back quoted, possibly split into several strings
+        .map(|a| a.data.split('"').filter(|part| !part.trim().is_empty()).collect::<String>())
+        .collect())
+}
+
+async fn read_body_prefix(mut response: reqwest::Response) -> String {
+    let mut body = Vec::new();
+    while let Ok(Some(chunk)) = response.chunk().await {
+        body.extend_from_slice(&chunk);
+        if body.len() >= MAX_BODY_BYTES {
+            break;
+        }
+    }
+    String::from_utf8_lossy(&body).into_owned()
+}
+
+/// Compare the hostnames of two URLs, case-insensitively
+pub fn same_host(a: &str, b: &str) -> bool {
+    match (host_of(a), host_of(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
+
+pub fn host_of(url: &str) -> Option<String> {
+    let parsed = Url::parse(url)
+        .or_else(|_| Url::parse(&format!("http://{}", url)))
+        .ok()?;
+    parsed.host_str().map(|h| h.to_ascii_lowercase())
+}
+
+/// Find `<meta http-equiv="onion-location" content="...">` in an HTML document
+fn meta_onion_location(html: &str) -> Option<String> {
+    // ASCII lowercasing keeps byte offsets identical to the original
+    let lower = html.to_ascii_lowercase();
+    let mut from = 0;
+
+    while let Some(pos) = lower[from..].find("<meta") {
+        let start = from + pos + "<meta".len();
+        let end = lower[start..].find('>').map(|e| start + e).unwrap_or(lower.len());
+        let attributes = parse_attributes(&html[start..end]);
+
+        let is_onion_location = attributes.get("http-equiv")
+            .map(|v| v.trim().eq_ignore_ascii_case("onion-location"))
+            .unwrap_or(false);
+        if is_onion_location {
+            if let Some(content) = attributes.get("content") {
+                return Some(content.trim().to_string());
+            }
+        }
+
+        from = end;
+    }
+
+    None
+}
+
+/// Parse `name="value"` pairs from the inside of a tag (names lowercased)
+fn parse_attributes(tag: &str) -> HashMap<String, String> {
+    let mut attributes = HashMap::new();
+    let mut chars = tag.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace() || *c == '/').is_some() {}
+
+        let name: String = std::iter::from_fn(|| chars.next_if(|c| !c.is_whitespace() && !matches!(c, '=' | '/')))
+            .collect();
+        if name.is_empty() {
+            break;
+        }
+
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let mut value = String::new();
+        if chars.next_if_eq(&'=').is_some() {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            match chars.peek() {
+                Some(&quote) if quote == '"' || quote == '\'' => {
+                    chars.next();
+                    value = std::iter::from_fn(|| chars.next_if(|c| *c != quote)).collect();
+                    chars.next();
+                }
+                _ => {
+                    value = std::iter::from_fn(|| chars.next_if(|c| !c.is_whitespace())).collect();
+                }
+            }
+        }
+
+        attributes.insert(name.to_ascii_lowercase(), value);
+    }
+
+    attributes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+
+    /// Answer one HTTP request with `response` and return the URL to request
+    fn serve_once(response: &'static str) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut [0; 4096]);
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+        url
+    }
+
+    #[test]
+    fn finds_onion_location_meta_tags() {
+        let html = r#"<html><head>
+            <meta charset="utf-8">
+            <meta name="description" content="http://decoy.onion">
+            <META HTTP-EQUIV="Onion-Location" CONTENT="http://abc.onion/">
+            <meta http-equiv="onion-location" content="http://second.onion/">
+        </head></html>"#;
+        assert_eq!(meta_onion_location(html).as_deref(), Some("http://abc.onion/"));
+
+        assert_eq!(
+            meta_onion_location("<meta http-equiv='onion-location' content=' http://abc.onion/path '/>").as_deref(),
+            Some("http://abc.onion/path")
+        );
+        assert_eq!(
+            meta_onion_location("<meta content=http://abc.onion http-equiv=onion-location>").as_deref(),
+            Some("http://abc.onion")
+        );
+        assert_eq!(meta_onion_location("<meta http-equiv=\"refresh\" content=\"0\">"), None);
+        assert_eq!(meta_onion_location("<p>onion-location http://abc.onion</p>"), None);
+    }
+
+    #[test]
+    fn parses_tag_attributes() {
+        let attributes = parse_attributes(r#" HTTP-EQUIV = "a b" content='it"s' data=bare async /"#);
+        assert_eq!(attributes["http-equiv"], "a b");
+        assert_eq!(attributes["content"], "it\"s");
+        assert_eq!(attributes["data"], "bare");
+        assert_eq!(attributes["async"], "");
+        assert_eq!(attributes.len(), 4);
+
+        // An unterminated quote runs to the end of the tag
+        assert_eq!(parse_attributes(r#"content="http://abc.onion"#)["content"], "http://abc.onion");
+    }
+
+    #[test]
+    fn compares_hosts() {
+        assert!(same_host("http://ABC.onion/", "https://abc.onion/other"));
+        assert!(same_host("abc.onion", "http://abc.onion:80"));
+        assert!(!same_host("http://abc.onion", "http://abc.onion.example.com"));
+        assert!(!same_host("", "http://abc.onion"));
+    }
+
+    #[tokio::test]
+    async fn prefers_the_header_over_the_meta_tag() {
+        const RESPONSE: &str = "HTTP/1.1 200 OK\r\nOnion-Location: http://header.onion/\r\nContent-Type: text/html\r\n\
+            Content-Length: 63\r\nConnection: close\r\n\r\n\
+            <meta http-equiv=\"onion-location\" content=\"http://meta.onion/\">";
+        let client = Client::builder().no_proxy().build().unwrap();
+
+        let check = check_onion_location(&client, &serve_once(RESPONSE), &["http://header.onion".to_string()]).await;
+        assert_eq!(check.status, VerificationStatus::Verified);
+        assert_eq!(check.source.as_deref(), Some("header"));
+
+        let check = check_onion_location(&client, &serve_once(RESPONSE), &["http://meta.onion".to_string()]).await;
+        assert_eq!(check.status, VerificationStatus::Mismatch);
+        assert_eq!(check.advertised.as_deref(), Some("http://header.onion/"));
+
+        let meta_only = "HTTP/1.1 200 OK\r\nContent-Length: 63\r\nConnection: close\r\n\r\n\
+            <meta http-equiv=\"onion-location\" content=\"http://meta.onion/\">";
+        let check = check_onion_location(&client, &serve_once(meta_only), &["http://meta.onion".to_string()]).await;
+        assert_eq!(check.status, VerificationStatus::Verified);
+        assert_eq!(check.source.as_deref(), Some("meta"));
+    }
+
+    #[tokio::test]
+    async fn ignores_onion_location_on_error_responses() {
+        const RESPONSE: &str = "HTTP/1.1 503 Service Unavailable\r\nOnion-Location: http://header.onion/\r\n\
+            Content-Length: 0\r\nConnection: close\r\n\r\n";
+        let client = Client::builder().no_proxy().build().unwrap();
+
+        let check = check_onion_location(&client, &serve_once(RESPONSE), &["http://header.onion".to_string()]).await;
+        assert_eq!(check.status, VerificationStatus::Error);
+        assert_eq!(check.advertised, None);
+        assert_eq!(check.error.as_deref(), Some("HTTP 503 Service Unavailable"));
+    }
+
+    fn concluded(outcomes: Vec<(ProofMethod, ProofOutcome)>, attempted: usize) -> OwnershipCheck {
+        let mut check = OwnershipCheck {
+            status: VerificationStatus::Unverified,
+            method: None,
+            details: Vec::new(),
+            checked_at: Utc::now(),
+            verified_at: None,
+        };
+        conclude(&mut check, outcomes, attempted);
+        check
+    }
+
+    #[test]
+    fn backlinks_alone_only_claim_ownership() {
+        let check = concluded(vec![(ProofMethod::OnionBacklink, ProofOutcome::Matches)], 1);
+        assert_eq!(check.status, VerificationStatus::Claimed);
+        assert_eq!(check.method, Some(ProofMethod::OnionBacklink));
+        assert_eq!(check.verified_at, None);
+        assert_eq!(check.details, ["onion-backlink: claimed by the onion"]);
+
+        let check = concluded(vec![
+            (ProofMethod::OnionBacklink, ProofOutcome::Matches),
+            (ProofMethod::WellKnown, ProofOutcome::NotPublished),
+        ], 2);
+        assert_eq!(check.status, VerificationStatus::Claimed);
+
+        // A clearnet proof backs the claim up
+        let check = concluded(vec![
+            (ProofMethod::OnionBacklink, ProofOutcome::Matches),
+            (ProofMethod::Dns, ProofOutcome::Matches),
+        ], 2);
+        assert_eq!(check.status, VerificationStatus::Verified);
+        assert_eq!(check.method, Some(ProofMethod::Dns));
+        assert!(check.verified_at.is_some());
+
+        // ...or contradicts it
+        let check = concluded(vec![
+            (ProofMethod::WellKnown, ProofOutcome::Conflicts),
+            (ProofMethod::OnionBacklink, ProofOutcome::Matches),
+        ], 2);
+        assert_eq!(check.status, VerificationStatus::Mismatch);
+
+        let check = concluded(vec![(ProofMethod::WellKnown, ProofOutcome::Failed("timeout".to_string()))], 1);
+        assert_eq!(check.status, VerificationStatus::Error);
+    }
+}